@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::credentials::{get_url, save_apply_and_wait, Token};
+use crate::curl;
+use crate::devices::{
+    get_allowlist_json, put_allowlist_json, Eui, DEVICE_PROFILE_ID_PREFIX,
+    NETWORK_PROFILE_ID_PREFIX,
+};
+use crate::result::MtcapError;
+
+const DEVICE_PROFILES_API: &str = "lora/device-profiles";
+
+const NETWORK_PROFILES_API: &str = "lora/network-profiles";
+
+/// Downloads the gateway's full configuration export.
+pub fn export(token: &Token) -> Result<Vec<u8>, MtcapError> {
+    curl::download(get_url(token, "command/download_config"))
+}
+
+pub fn export_to_file(token: &Token, path: &Path) -> Result<(), MtcapError> {
+    fs::write(path, export(token)?)?;
+
+    Ok(())
+}
+
+/// Uploads a full configuration export, then saves and applies it.
+pub fn restore(token: &Token, configuration: &[u8]) -> Result<(), MtcapError> {
+    curl::upload_bytes(get_url(token, "command/upload_config"), configuration)?;
+
+    save_apply_and_wait(token)
+}
+
+pub fn restore_from_file(token: &Token, path: &Path) -> Result<(), MtcapError> {
+    curl::upload(get_url(token, "command/upload_config"), path)?;
+
+    save_apply_and_wait(token)
+}
+
+/// The LoRa network server settings, allowlist and profiles of a gateway. Everything is kept as
+/// the gateway returned it, including allowlist entries with profiles this library does not know.
+#[derive(Clone, PartialEq)]
+pub struct LoraSnapshot {
+    lora: json::JsonValue,
+    allowlist: json::JsonValue,
+    device_profiles: json::JsonValue,
+    network_profiles: json::JsonValue,
+}
+
+/// The differences from one [`LoraSnapshot`] to another.
+#[derive(Debug, Default, PartialEq)]
+pub struct SnapshotDiff {
+    pub lora_changed: bool,
+    pub allowlist_enabled_changed: bool,
+    pub devices_added: Vec<Eui>,
+    pub devices_removed: Vec<Eui>,
+    pub devices_changed: Vec<Eui>,
+    pub device_profiles_changed: bool,
+    pub network_profiles_changed: bool,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What to do to a gateway's profiles to make them match a snapshot.
+#[derive(Debug, Default, PartialEq)]
+struct ProfileChanges<'a> {
+    update: Vec<&'a json::JsonValue>,
+    create: Vec<&'a json::JsonValue>,
+    delete: Vec<String>,
+}
+
+impl<'a> ProfileChanges<'a> {
+    fn new(current: &json::JsonValue, wanted: &'a json::JsonValue) -> Self {
+        let id = |profile: &json::JsonValue| profile["id"].to_string();
+        let current_ids = current.members().map(id).collect::<Vec<_>>();
+        let wanted_ids = wanted.members().map(id).collect::<Vec<_>>();
+
+        let mut changes = Self::default();
+        for profile in wanted.members() {
+            if current_ids.contains(&id(profile)) {
+                changes.update.push(profile);
+            } else {
+                changes.create.push(profile);
+            }
+        }
+        changes.delete = current_ids
+            .into_iter()
+            .filter(|profile_id| !wanted_ids.contains(profile_id) && !is_built_in(profile_id))
+            .collect();
+
+        changes
+    }
+}
+
+impl LoraSnapshot {
+    pub fn take(token: &Token) -> Result<Self, MtcapError> {
+        let gateway_response = curl::get(get_url(token, "loraNetwork/lora"))?;
+        let lora = json::parse(&gateway_response)?["result"].clone();
+
+        let allowlist = get_allowlist_json(token)?;
+
+        let gateway_response = curl::get(get_url(token, DEVICE_PROFILES_API))?;
+        let device_profiles = json::parse(&gateway_response)?["result"].clone();
+
+        let gateway_response = curl::get(get_url(token, NETWORK_PROFILES_API))?;
+        let network_profiles = json::parse(&gateway_response)?["result"].clone();
+
+        Ok(Self {
+            lora,
+            allowlist,
+            device_profiles,
+            network_profiles,
+        })
+    }
+
+    /// Writes the snapshot back to the gateway, replacing its allowlist and profiles, then saves
+    /// and applies it. Profiles which are not in the snapshot are deleted, except those which come
+    /// with the firmware.
+    pub fn restore(&self, token: &Token) -> Result<(), MtcapError> {
+        curl::put(get_url(token, "loraNetwork/lora"), self.lora.clone())?;
+
+        let mut deletions = Vec::new();
+        for (api, profiles) in [
+            (DEVICE_PROFILES_API, &self.device_profiles),
+            (NETWORK_PROFILES_API, &self.network_profiles),
+        ] {
+            let gateway_response = curl::get(get_url(token, api))?;
+            let current = &json::parse(&gateway_response)?["result"];
+            let changes = ProfileChanges::new(current, profiles);
+
+            for profile in changes.update {
+                curl::put(
+                    get_url(token, format!("{api}/{}", profile["id"])),
+                    profile.clone(),
+                )?;
+            }
+            for profile in changes.create {
+                curl::post_json(get_url(token, api), profile.clone())?;
+            }
+            deletions.extend(
+                changes
+                    .delete
+                    .into_iter()
+                    .map(|profile_id| format!("{api}/{profile_id}")),
+            );
+        }
+
+        put_allowlist_json(token, self.allowlist.clone())?;
+
+        // Only once the allowlist no longer refers to them.
+        for path in deletions {
+            curl::delete(get_url(token, path))?;
+        }
+
+        save_apply_and_wait(token)
+    }
+
+    /// The allowlist entries, as the gateway returned them.
+    pub fn allowlist(&self) -> &json::JsonValue {
+        &self.allowlist["devices"]
+    }
+
+    pub fn allowlist_enabled(&self) -> bool {
+        self.allowlist["enabled"].as_bool() == Some(true)
+    }
+
+    /// Compares the snapshots. Allowlist entries are matched by device EUI; entries without a
+    /// valid one are not compared.
+    pub fn diff(&self, other: &Self) -> SnapshotDiff {
+        let entries = |snapshot: &Self| {
+            snapshot
+                .allowlist()
+                .members()
+                .filter_map(|entry| Some((entry_eui(entry)?, entry.clone())))
+                .collect::<Vec<_>>()
+        };
+        let (before, after) = (entries(self), entries(other));
+        let find = |entries: &[(Eui, json::JsonValue)], device_eui: &Eui| {
+            entries
+                .iter()
+                .find(|(entry_eui, _)| entry_eui == device_eui)
+                .map(|(_, entry)| entry.clone())
+        };
+
+        let mut diff = SnapshotDiff {
+            lora_changed: self.lora != other.lora,
+            allowlist_enabled_changed: self.allowlist_enabled() != other.allowlist_enabled(),
+            device_profiles_changed: self.device_profiles != other.device_profiles,
+            network_profiles_changed: self.network_profiles != other.network_profiles,
+            ..Default::default()
+        };
+        for (device_eui, entry) in &after {
+            match find(&before, device_eui) {
+                None => diff.devices_added.push(device_eui.clone()),
+                Some(previous) if previous != *entry => {
+                    diff.devices_changed.push(device_eui.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        for (device_eui, _) in &before {
+            if find(&after, device_eui).is_none() {
+                diff.devices_removed.push(device_eui.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Serialises the snapshot as pretty-printed JSON, with the allowlist sorted by device EUI so
+    /// that snapshots diff cleanly as text.
+    pub fn to_json_string(&self) -> String {
+        json::stringify_pretty(self.to_json(), 2)
+    }
+
+    pub fn from_json_string(input: &str) -> Result<Self, MtcapError> {
+        Ok(Self::from_json(&json::parse(input)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MtcapError> {
+        fs::write(path, self.to_json_string())?;
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, MtcapError> {
+        Self::from_json_string(&fs::read_to_string(path)?)
+    }
+
+    fn to_json(&self) -> json::JsonValue {
+        let mut devices = self.allowlist().members().cloned().collect::<Vec<_>>();
+        devices.sort_by_key(entry_eui);
+
+        let mut allowlist = self.allowlist.clone();
+        allowlist["devices"] = devices.into();
+
+        json::object! {
+            lora: self.lora.clone(),
+            allowlist: allowlist,
+            device_profiles: self.device_profiles.clone(),
+            network_profiles: self.network_profiles.clone(),
+        }
+    }
+
+    fn from_json(json: &json::JsonValue) -> Self {
+        Self {
+            lora: json["lora"].clone(),
+            allowlist: json["allowlist"].clone(),
+            device_profiles: json["device_profiles"].clone(),
+            network_profiles: json["network_profiles"].clone(),
+        }
+    }
+}
+
+/// Whether the profile comes with the firmware, so is kept even when a snapshot from other
+/// firmware lacks it.
+fn is_built_in(profile_id: &str) -> bool {
+    profile_id.starts_with(DEVICE_PROFILE_ID_PREFIX)
+        || profile_id.starts_with(NETWORK_PROFILE_ID_PREFIX)
+}
+
+fn entry_eui(entry: &json::JsonValue) -> Option<Eui> {
+    Eui::from_str(&entry["deveui"].to_string()).ok()
+}
+
+#[cfg(test)]
+#[path = "./test_backup.rs"]
+mod test_backup;
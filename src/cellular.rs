@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use strum_macros::{Display, EnumString};
+
+use crate::credentials::{get_url, save_apply, Token};
+use crate::curl;
+use crate::result::MtcapError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Registration {
+    NotRegistered,
+    Home,
+    Searching,
+    Denied,
+    Roaming,
+    Unknown,
+}
+
+impl Registration {
+    fn parse(registration: &str) -> Self {
+        match registration
+            .to_ascii_uppercase()
+            .replace([' ', '_', '-'], "")
+            .as_str()
+        {
+            "NOTREGISTERED" | "0" => Self::NotRegistered,
+            "REGISTERED" | "HOME" | "HOMENETWORK" | "1" => Self::Home,
+            "SEARCHING" | "2" => Self::Searching,
+            "DENIED" | "REGISTRATIONDENIED" | "3" => Self::Denied,
+            "ROAMING" | "5" => Self::Roaming,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellularStatus {
+    rssi: Option<i32>,
+    rsrp: Option<i32>,
+    carrier: String,
+    registration: Registration,
+    imei: String,
+    iccid: String,
+    bytes_received: Option<u64>,
+    bytes_sent: Option<u64>,
+}
+
+impl CellularStatus {
+    /// Received signal strength in dBm.
+    pub fn rssi(&self) -> Option<i32> {
+        self.rssi
+    }
+
+    /// LTE reference signal received power in dBm.
+    pub fn rsrp(&self) -> Option<i32> {
+        self.rsrp
+    }
+
+    pub fn carrier(&self) -> &str {
+        &self.carrier
+    }
+
+    pub fn registration(&self) -> Registration {
+        self.registration
+    }
+
+    pub fn imei(&self) -> &str {
+        &self.imei
+    }
+
+    pub fn iccid(&self) -> &str {
+        &self.iccid
+    }
+
+    /// Bytes received over cellular since the counters were last reset.
+    pub fn bytes_received(&self) -> Option<u64> {
+        self.bytes_received
+    }
+
+    /// Bytes sent over cellular since the counters were last reset.
+    pub fn bytes_sent(&self) -> Option<u64> {
+        self.bytes_sent
+    }
+
+    fn from_json(json: &json::JsonValue) -> Self {
+        let string = |key: &str| json[key].as_str().unwrap_or_default().trim().to_string();
+
+        Self {
+            rssi: number(&json["rssidBm"]).or_else(|| number(&json["rssi"])),
+            rsrp: number(&json["rsrp"]),
+            carrier: string("network"),
+            registration: Registration::parse(&string("netReg")),
+            imei: string("imei"),
+            iccid: string("iccid"),
+            bytes_received: number(&json["rxBytes"]),
+            bytes_sent: number(&json["txBytes"]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
+pub enum Authentication {
+    #[strum(serialize = "NONE")]
+    None,
+    #[strum(serialize = "PAP")]
+    Pap,
+    #[strum(serialize = "CHAP")]
+    Chap,
+    #[strum(serialize = "PAP-CHAP")]
+    PapChap,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellularConfig {
+    pub enabled: bool,
+    pub apn: String,
+    pub authentication: Authentication,
+    pub username: String,
+    pub password: String,
+}
+
+impl CellularConfig {
+    fn from_json(json: &json::JsonValue) -> Result<Self, MtcapError> {
+        let string = |key: &str| json[key].as_str().unwrap_or_default().to_string();
+
+        let authentication = match json["authType"].as_str().unwrap_or_default() {
+            "" => Authentication::None,
+            authentication => authentication.parse().map_err(|_| {
+                MtcapError::Other(format!("Unknown cellular authentication {authentication}"))
+            })?,
+        };
+
+        Ok(Self {
+            enabled: json["enabled"].as_bool() == Some(true),
+            apn: string("apn"),
+            authentication,
+            username: string("username"),
+            password: string("password"),
+        })
+    }
+
+    fn update_json(&self, json: &mut json::JsonValue) {
+        json["enabled"] = self.enabled.into();
+        json["apn"] = self.apn.as_str().into();
+        json["authType"] = self.authentication.to_string().into();
+        json["username"] = self.username.as_str().into();
+        json["password"] = self.password.as_str().into();
+    }
+}
+
+pub fn status(token: &Token) -> Result<CellularStatus, MtcapError> {
+    let response = curl::get(get_url(token, "stats/radio"))?;
+
+    Ok(CellularStatus::from_json(
+        &json::parse(&response)?["result"],
+    ))
+}
+
+pub fn get_config(token: &Token) -> Result<CellularConfig, MtcapError> {
+    let response = curl::get(get_url(token, "radio"))?;
+
+    CellularConfig::from_json(&json::parse(&response)?["result"])
+}
+
+/// Sets the cellular configuration, then saves and applies it without waiting, as changing the
+/// APN drops the cellular link. A gateway reached over that link can be reconnected to with
+/// [`crate::system::wait_for_login`] once the modem has registered again.
+pub fn set_config(token: &Token, config: &CellularConfig) -> Result<(), MtcapError> {
+    let response = curl::get(get_url(token, "radio"))?;
+    let mut json = json::parse(&response)?["result"].clone();
+    config.update_json(&mut json);
+
+    curl::put(get_url(token, "radio"), json)?;
+
+    save_apply(token)?;
+
+    Ok(())
+}
+
+/// Reads a number given either as a JSON number or as a string, as firmware versions differ.
+fn number<T: FromStr + TryFrom<i64>>(json: &json::JsonValue) -> Option<T> {
+    match json.as_i64() {
+        Some(number) => T::try_from(number).ok(),
+        None => json.as_str()?.trim().parse().ok(),
+    }
+}
+
+#[cfg(test)]
+#[path = "./test_cellular.rs"]
+mod test_cellular;
@@ -0,0 +1,117 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::credentials::{get_url, save_apply, Token};
+use crate::curl;
+use crate::devices::parse_timestamp;
+use crate::result::MtcapError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GatewayTime {
+    time: DateTime<Utc>,
+    time_zone: String,
+}
+
+impl GatewayTime {
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// The configured time zone, for example `UTC` or `Europe/Berlin`.
+    pub fn time_zone(&self) -> &str {
+        &self.time_zone
+    }
+
+    fn from_json(json: &json::JsonValue) -> Result<Self, MtcapError> {
+        let time = parse_timestamp(&json["dateTime"])
+            .map_err(MtcapError::Other)?
+            .ok_or_else(|| MtcapError::Other("The gateway did not report its time".to_string()))?;
+
+        Ok(Self {
+            time,
+            time_zone: json["timeZone"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NtpConfig {
+    pub enabled: bool,
+    pub servers: Vec<String>,
+}
+
+impl NtpConfig {
+    fn from_json(json: &json::JsonValue) -> Self {
+        Self {
+            enabled: json["enabled"].as_bool() == Some(true),
+            servers: json["servers"]
+                .members()
+                .filter_map(|server| server.as_str())
+                .filter(|server| !server.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    fn update_json(&self, json: &mut json::JsonValue) {
+        json["enabled"] = self.enabled.into();
+        json["servers"] = self.servers.clone().into();
+    }
+}
+
+pub fn time(token: &Token) -> Result<GatewayTime, MtcapError> {
+    let gateway_response = curl::get(get_url(token, "system"))?;
+
+    GatewayTime::from_json(&json::parse(&gateway_response)?["result"])
+}
+
+/// How far the gateway's clock is ahead of the host's, negative if behind.
+///
+/// The host time is taken halfway through the request, so the result is accurate to about half the
+/// request's round trip plus the gateway's one second resolution.
+pub fn skew(token: &Token) -> Result<TimeDelta, MtcapError> {
+    let before = Utc::now();
+    let gateway_time = time(token)?.time;
+    let after = Utc::now();
+
+    Ok(gateway_time - (before + (after - before) / 2))
+}
+
+/// Fails if the gateway's clock differs from the host's by more than `tolerance`.
+pub fn check_skew(token: &Token, tolerance: TimeDelta) -> Result<TimeDelta, MtcapError> {
+    let skew = skew(token)?;
+
+    if skew.abs() > tolerance {
+        Err(MtcapError::Other(format!(
+            "The gateway clock is off by {} seconds",
+            skew.num_seconds()
+        )))
+    } else {
+        Ok(skew)
+    }
+}
+
+pub fn get_ntp(token: &Token) -> Result<NtpConfig, MtcapError> {
+    let gateway_response = curl::get(get_url(token, "ntp"))?;
+
+    Ok(NtpConfig::from_json(
+        &json::parse(&gateway_response)?["result"],
+    ))
+}
+
+/// Sets the NTP configuration, then saves and applies it. This does not restart the LoRa network
+/// server, so the apply is not waited for.
+pub fn set_ntp(token: &Token, config: &NtpConfig) -> Result<(), MtcapError> {
+    let gateway_response = curl::get(get_url(token, "ntp"))?;
+    let mut json = json::parse(&gateway_response)?["result"].clone();
+    config.update_json(&mut json);
+
+    curl::put(get_url(token, "ntp"), json)?;
+
+    save_apply(token)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "./test_clock.rs"]
+mod test_clock;
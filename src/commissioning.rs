@@ -0,0 +1,98 @@
+use crate::credentials::{login, Address, Gateway, Token};
+use crate::curl;
+use crate::devices::{self, Device};
+use crate::network::{self, Mode};
+use crate::result::MtcapError;
+
+/// The gateway asks for the password, then for it again to confirm, and may show information
+/// steps around them. Any more steps than this means it is asking again in a loop.
+const MAX_CHALLENGES: usize = 4;
+
+/// What to configure on a gateway once it is commissioned.
+#[derive(Default)]
+pub struct Provisioning {
+    pub mode: Option<Mode>,
+    pub devices: Vec<Device>,
+}
+
+/// Whether the gateway still needs its initial admin account set, as after a factory reset.
+pub fn is_required(address: &Address) -> Result<bool, MtcapError> {
+    let response = curl::get(commissioning_url(address))?;
+    let json = &json::parse(&response)?["result"];
+
+    Ok(json["username"].as_str().unwrap_or_default().is_empty()
+        && json["commissioned"].as_bool() != Some(true))
+}
+
+/// Sets the gateway's initial admin username and password to those of `gateway`, then logs in.
+pub fn commission(gateway: &Gateway) -> Result<Token, MtcapError> {
+    let url = commissioning_url(gateway.address());
+
+    let mut request = json::object! { username: gateway.username() };
+    for _ in 0..MAX_CHALLENGES {
+        let response = curl::post_json_with_response(url.clone(), request.clone())?;
+        match answer(&json::parse(&response)?["result"], gateway)? {
+            Some(answer) => request = answer,
+            None => return login(gateway),
+        }
+    }
+
+    Err(MtcapError::Other(
+        "Commissioning did not finish".to_string(),
+    ))
+}
+
+/// The request answering a commissioning step, or `None` once commissioning is done.
+fn answer(
+    challenge: &json::JsonValue,
+    gateway: &Gateway,
+) -> Result<Option<json::JsonValue>, MtcapError> {
+    let challenge_id = challenge["aasID"].as_str().unwrap_or_default();
+    if challenge_id.is_empty() || challenge["aasDone"].as_bool() == Some(true) {
+        return Ok(None);
+    }
+
+    let answer = match challenge["aasType"].as_str().unwrap_or_default() {
+        "password" | "confirm" | "password confirm" => gateway.password(),
+        "info" => "",
+        other => {
+            return Err(MtcapError::Other(format!(
+                "Unexpected commissioning step {other}: {}",
+                challenge["aasMsg"]
+            )))
+        }
+    };
+
+    Ok(Some(json::object! {
+        username: gateway.username(),
+        aasID: challenge_id,
+        aasAnswer: answer,
+    }))
+}
+
+/// Takes a gateway from out of the box to configured: commissions it if needed, logs in, and
+/// applies `provisioning`.
+pub fn provision(gateway: &Gateway, provisioning: &Provisioning) -> Result<Token, MtcapError> {
+    let token = if is_required(gateway.address())? {
+        commission(gateway)?
+    } else {
+        login(gateway)?
+    };
+
+    if let Some(mode) = provisioning.mode {
+        network::set_mode(&token, mode)?;
+    }
+    if !provisioning.devices.is_empty() {
+        devices::add(&token, &provisioning.devices)?;
+    }
+
+    Ok(token)
+}
+
+fn commissioning_url(address: &Address) -> String {
+    format!("{address}/api/commissioning")
+}
+
+#[cfg(test)]
+#[path = "./test_commissioning.rs"]
+mod test_commissioning;
@@ -1,82 +1,327 @@
 use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::curl;
+use crate::network::{get_mode, Mode};
 use crate::result::MtcapError;
+use crate::system::{lora_answers, poll_until, wait_for_lora};
 
 const IPV4_LENGTH: usize = 4;
 
-#[derive(Clone)]
-struct Ipv4 {
-    digits: [u8; IPV4_LENGTH],
+const DEFAULT_SCHEME: &str = "https";
+
+const DEFAULT_APPLY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long after an apply to watch for the LoRa network server going down, and how often.
+const APPLY_RESTART_WINDOW: Duration = Duration::from_secs(15);
+const APPLY_RESTART_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a gateway's API is reached: `scheme://host:port/base_path/api/...`.
+///
+/// Parses from forms such as `192.168.2.1`, `gateway.example.com:8443`, `[fd00::1]` or
+/// `http://10.0.0.1:8080/site-a`. The scheme defaults to `https`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Address {
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+    base_path: String,
 }
 
-impl fmt::Display for Ipv4 {
+impl fmt::Display for Address {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}.{}.{}.{}",
-            self.digits[0], self.digits[1], self.digits[2], self.digits[3],
-        )
+        write!(f, "{}://", self.scheme)?;
+        if self.host.parse::<Ipv6Addr>().is_ok() {
+            write!(f, "[{}]", self.host)?;
+        } else {
+            write!(f, "{}", self.host)?;
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        write!(f, "{}", self.base_path)
     }
 }
 
-impl Ipv4 {
-    fn new(ip: [u8; IPV4_LENGTH]) -> Self {
-        Self { digits: ip }
+impl FromStr for Address {
+    type Err = MtcapError;
+
+    fn from_str(input: &str) -> Result<Self, MtcapError> {
+        let invalid =
+            |reason: &str| MtcapError::Other(format!("{input} is not a valid address: {reason}"));
+
+        let (scheme, rest) = match input.split_once("://") {
+            Some((scheme, rest)) => (scheme, rest),
+            None => (DEFAULT_SCHEME, input),
+        };
+        let (authority, base_path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            let (host, after) = bracketed
+                .split_once(']')
+                .ok_or_else(|| invalid("unclosed ["))?;
+            let port = match after {
+                "" => None,
+                _ => Some(
+                    after
+                        .strip_prefix(':')
+                        .ok_or_else(|| invalid("expected :port after ]"))?,
+                ),
+            };
+            (host, port)
+        } else if authority.matches(':').count() == 1 {
+            let (host, port) = authority.split_once(':').unwrap();
+            (host, Some(port))
+        } else {
+            (authority, None)
+        };
+
+        if host.is_empty() {
+            return Err(invalid("no host"));
+        }
+        if host.contains(':') && host.parse::<Ipv6Addr>().is_err() {
+            return Err(invalid("malformed IPv6 address"));
+        }
+        let port = port
+            .map(|port| port.parse::<u16>().map_err(|_| invalid("malformed port")))
+            .transpose()?;
+
+        Ok(Self {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            port,
+            base_path: base_path.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl From<IpAddr> for Address {
+    fn from(ip: IpAddr) -> Self {
+        Self::new(ip.to_string())
+    }
+}
+
+impl Address {
+    /// Creates an address for a host name, IPv4 address or IPv6 address, over HTTPS on the default
+    /// port.
+    pub fn new<T: Into<String>>(host: T) -> Self {
+        Self {
+            scheme: DEFAULT_SCHEME.to_string(),
+            host: host.into(),
+            port: None,
+            base_path: String::new(),
+        }
+    }
+
+    pub fn with_host<T: Into<String>>(mut self, host: T) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    pub fn with_scheme<T: Into<String>>(mut self, scheme: T) -> Self {
+        self.scheme = scheme.into();
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Sets a path to prepend to `/api`, for gateways behind a reverse proxy.
+    pub fn with_base_path<T: AsRef<str>>(mut self, base_path: T) -> Self {
+        let base_path = base_path.as_ref().trim_matches('/');
+        self.base_path = if base_path.is_empty() {
+            String::new()
+        } else {
+            format!("/{base_path}")
+        };
+        self
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    pub fn base_path(&self) -> &str {
+        &self.base_path
     }
 }
 
 pub struct Gateway {
-    ip: Ipv4,
+    address: Address,
     username: String,
     password: String,
 }
 
 impl Gateway {
     pub fn new(ip: [u8; IPV4_LENGTH], username: String, password: String) -> Self {
+        Self::with_address(IpAddr::from(ip).into(), username, password)
+    }
+
+    pub fn with_address(address: Address, username: String, password: String) -> Self {
         Self {
-            ip: Ipv4::new(ip),
+            address,
             username,
             password,
         }
     }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub(crate) fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// The same gateway, with another password.
+    pub(crate) fn with_password(&self, password: String) -> Self {
+        Self::with_address(self.address.clone(), self.username.clone(), password)
+    }
+
+    /// The same gateway, at another address.
+    pub(crate) fn relocated(&self, address: Address) -> Self {
+        Self::with_address(address, self.username.clone(), self.password.clone())
+    }
 }
 
+#[derive(Clone)]
 pub struct Token {
-    ip: Ipv4,
+    address: Address,
     token: String,
+    apply_timeout: Option<Duration>,
+    snapshot_dir: Option<PathBuf>,
 }
 
 impl Token {
-    fn new(ip: Ipv4, token: String) -> Self {
-        Self { ip, token }
+    pub(crate) fn new(address: Address, token: String) -> Self {
+        Self {
+            address,
+            token,
+            apply_timeout: Some(DEFAULT_APPLY_TIMEOUT),
+            snapshot_dir: None,
+        }
+    }
+
+    /// Sets how long functions which change the configuration wait for the gateway to apply it.
+    /// `None` returns as soon as the gateway accepts the change.
+    pub fn with_apply_timeout(mut self, apply_timeout: Option<Duration>) -> Self {
+        self.apply_timeout = apply_timeout;
+        self
+    }
+
+    pub fn apply_timeout(&self) -> Option<Duration> {
+        self.apply_timeout
+    }
+
+    /// Sets a directory in which device removals save a [`crate::devices::DeviceSnapshot`] before
+    /// changing anything, for use with [`crate::devices::rollback`]. `None`, the default, saves
+    /// nothing.
+    pub fn with_snapshot_dir(mut self, snapshot_dir: Option<PathBuf>) -> Self {
+        self.snapshot_dir = snapshot_dir;
+        self
+    }
+
+    pub fn snapshot_dir(&self) -> Option<&Path> {
+        self.snapshot_dir.as_deref()
     }
 
     pub fn token(&self) -> &str {
         &self.token
     }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
 }
 
 pub fn login(gateway: &Gateway) -> Result<Token, MtcapError> {
-    let response = curl::get(format!(
-        "https://{}/api/login?username={}&password={}",
-        gateway.ip, gateway.username, gateway.password
-    ))?;
+    let response = curl::get(login_url(gateway))?;
 
     let json = json::parse(&response)?;
     let token_string = json["result"]["token"].to_string();
 
-    let token = Token::new(gateway.ip.clone(), token_string);
+    let token = Token::new(gateway.address.clone(), token_string);
 
     Ok(token)
 }
 
+pub fn save(token: &Token) -> Result<(), MtcapError> {
+    curl::post(get_url(token, "command/save"))?;
+
+    Ok(())
+}
+
+/// Applies the saved configuration. The gateway restarts the LoRa network server in the
+/// background; see [`wait_for_apply`].
+pub fn apply(token: &Token) -> Result<(), MtcapError> {
+    curl::post(get_url(token, "command/apply"))?;
+
+    Ok(())
+}
+
+/// Saves and applies the configuration, without waiting for the apply to finish.
 pub fn save_apply(token: &Token) -> Result<(), MtcapError> {
     curl::post(get_url(token, "command/save_apply"))?;
 
     Ok(())
 }
 
+/// Waits for an apply to finish, that is for the LoRa network server to go down and come back
+/// up. If it keeps answering for a while, the apply is taken not to have restarted it. When the
+/// LoRa mode is not [`Mode::NetworkServer`] there is no server to wait for, so this returns once
+/// the configuration can be read back.
+pub fn wait_for_apply(token: &Token, timeout: Duration) -> Result<(), MtcapError> {
+    let deadline = Instant::now() + timeout;
+
+    let mut mode = None;
+    poll_until(deadline, "the configuration", || {
+        mode = get_mode(token).ok();
+        mode.is_some()
+    })?;
+    if mode != Some(Mode::NetworkServer) {
+        return Ok(());
+    }
+
+    let window = deadline.min(Instant::now() + APPLY_RESTART_WINDOW);
+    while Instant::now() < window && lora_answers(token) {
+        thread::sleep(APPLY_RESTART_POLL_INTERVAL);
+    }
+
+    wait_for_lora(token, deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Saves and applies the configuration, then waits according to the token's apply timeout.
+pub(crate) fn save_apply_and_wait(token: &Token) -> Result<(), MtcapError> {
+    save_apply(token)?;
+
+    if let Some(timeout) = token.apply_timeout {
+        wait_for_apply(token, timeout)?;
+    }
+
+    Ok(())
+}
+
 pub fn logout(token: &Token) -> Result<(), MtcapError> {
     curl::get(get_url(token, "logout"))?;
 
@@ -84,5 +329,40 @@ pub fn logout(token: &Token) -> Result<(), MtcapError> {
 }
 
 pub fn get_url<T: fmt::Display>(token: &Token, api: T) -> String {
-    format!("https://{}/api/{api}?token={}", token.ip, token.token)
+    format!("{}/api/{api}?token={}", token.address, token.token)
+}
+
+/// The URL without the token, for requests which send it as a cookie.
+pub(crate) fn get_url_without_token<T: fmt::Display>(token: &Token, api: T) -> String {
+    format!("{}/api/{api}", token.address)
+}
+
+pub fn get_url_with_query<T: fmt::Display>(token: &Token, api: T, query: &str) -> String {
+    format!("{}/api/{api}?{query}&token={}", token.address, token.token)
 }
+
+fn login_url(gateway: &Gateway) -> String {
+    format!(
+        "{}/api/login?username={}&password={}",
+        gateway.address,
+        percent_encode(&gateway.username),
+        percent_encode(&gateway.password)
+    )
+}
+
+/// Encodes everything but unreserved characters, for user input in a path segment or query.
+pub(crate) fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./test_credentials.rs"]
+mod test_credentials;
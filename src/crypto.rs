@@ -0,0 +1,246 @@
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use cmac::{Cmac, Mac};
+
+use crate::devices::Key;
+use crate::result::MtcapError;
+
+const BLOCK_LENGTH: usize = 16;
+
+pub const MIC_LENGTH: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Uplink,
+    Downlink,
+}
+
+impl Direction {
+    const fn to_u8(self) -> u8 {
+        match self {
+            Self::Uplink => 0,
+            Self::Downlink => 1,
+        }
+    }
+}
+
+pub struct SessionKeys {
+    network_session_key: Key,
+    application_session_key: Key,
+}
+
+impl SessionKeys {
+    pub fn network_session_key(&self) -> &Key {
+        &self.network_session_key
+    }
+
+    pub fn application_session_key(&self) -> &Key {
+        &self.application_session_key
+    }
+}
+
+/// Derives the LoRaWAN 1.0.x session keys established by a join.
+///
+/// `join_nonce` and `net_id` are 24-bit values, as carried in the join-accept.
+pub fn derive_session_keys(
+    application_key: &Key,
+    join_nonce: u32,
+    net_id: u32,
+    device_nonce: u16,
+) -> SessionKeys {
+    let derive = |key_type: u8| {
+        let mut block = [0; BLOCK_LENGTH];
+        block[0] = key_type;
+        block[1..4].copy_from_slice(&join_nonce.to_le_bytes()[..3]);
+        block[4..7].copy_from_slice(&net_id.to_le_bytes()[..3]);
+        block[7..9].copy_from_slice(&device_nonce.to_le_bytes());
+        Key::new(aes128_encrypt(application_key, block))
+    };
+
+    SessionKeys {
+        network_session_key: derive(0x01),
+        application_session_key: derive(0x02),
+    }
+}
+
+/// Computes the MIC of a data frame, where `message` is the PHYPayload without its MIC.
+pub fn data_mic(
+    network_session_key: &Key,
+    direction: Direction,
+    device_address: u32,
+    frame_count: u32,
+    message: &[u8],
+) -> [u8; MIC_LENGTH] {
+    let mut data = Vec::with_capacity(BLOCK_LENGTH + message.len());
+    data.push(0x49);
+    data.extend_from_slice(&[0; 4]);
+    data.push(direction.to_u8());
+    data.extend_from_slice(&device_address.to_le_bytes());
+    data.extend_from_slice(&frame_count.to_le_bytes());
+    data.push(0x00);
+    data.push(message.len() as u8);
+    data.extend_from_slice(message);
+
+    truncate_mic(aes128_cmac(network_session_key, &data))
+}
+
+pub fn verify_data_mic(
+    network_session_key: &Key,
+    direction: Direction,
+    device_address: u32,
+    frame_count: u32,
+    phy_payload: &[u8],
+) -> Result<(), MtcapError> {
+    let (message, mic) = split_mic(phy_payload)?;
+    let expected = data_mic(
+        network_session_key,
+        direction,
+        device_address,
+        frame_count,
+        message,
+    );
+    check_mic(mic, &expected)
+}
+
+/// Computes the MIC of a join-request or of a decrypted join-accept, where `message` is the
+/// PHYPayload without its MIC.
+pub fn join_mic(application_key: &Key, message: &[u8]) -> [u8; MIC_LENGTH] {
+    truncate_mic(aes128_cmac(application_key, message))
+}
+
+/// Verifies the MIC of a join-request or of a join-accept already passed through
+/// [`decrypt_join_accept`].
+pub fn verify_join_mic(application_key: &Key, phy_payload: &[u8]) -> Result<(), MtcapError> {
+    let (message, mic) = split_mic(phy_payload)?;
+    check_mic(mic, &join_mic(application_key, message))
+}
+
+/// Decrypts a join-accept PHYPayload, returning the MHDR followed by the plaintext fields and MIC.
+pub fn decrypt_join_accept(
+    application_key: &Key,
+    phy_payload: &[u8],
+) -> Result<Vec<u8>, MtcapError> {
+    let Some((mhdr, encrypted)) = phy_payload.split_first() else {
+        return Err(MtcapError::Other("Join-accept is empty".to_string()));
+    };
+    if encrypted.is_empty() || encrypted.len() % BLOCK_LENGTH != 0 {
+        return Err(MtcapError::Other(format!(
+            "Join-accept payload length {} is not a multiple of {BLOCK_LENGTH}",
+            encrypted.len()
+        )));
+    }
+
+    // The network server encrypts with AES decrypt so that devices only need AES encrypt.
+    let mut output = vec![*mhdr];
+    for chunk in encrypted.chunks(BLOCK_LENGTH) {
+        output.extend_from_slice(&aes128_encrypt(application_key, chunk.try_into().unwrap()));
+    }
+
+    Ok(output)
+}
+
+/// Encrypts a join-accept PHYPayload given as the MHDR followed by the plaintext fields and MIC.
+pub fn encrypt_join_accept(
+    application_key: &Key,
+    phy_payload: &[u8],
+) -> Result<Vec<u8>, MtcapError> {
+    let Some((mhdr, plaintext)) = phy_payload.split_first() else {
+        return Err(MtcapError::Other("Join-accept is empty".to_string()));
+    };
+    if plaintext.is_empty() || plaintext.len() % BLOCK_LENGTH != 0 {
+        return Err(MtcapError::Other(format!(
+            "Join-accept payload length {} is not a multiple of {BLOCK_LENGTH}",
+            plaintext.len()
+        )));
+    }
+
+    let mut output = vec![*mhdr];
+    for chunk in plaintext.chunks(BLOCK_LENGTH) {
+        output.extend_from_slice(&aes128_decrypt(application_key, chunk.try_into().unwrap()));
+    }
+
+    Ok(output)
+}
+
+/// Encrypts an FRMPayload. The key is the network session key for port 0, and the application
+/// session key otherwise.
+pub fn encrypt_frm_payload(
+    key: &Key,
+    direction: Direction,
+    device_address: u32,
+    frame_count: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(payload.len());
+    for (i, chunk) in payload.chunks(BLOCK_LENGTH).enumerate() {
+        let mut block = [0; BLOCK_LENGTH];
+        block[0] = 0x01;
+        block[5] = direction.to_u8();
+        block[6..10].copy_from_slice(&device_address.to_le_bytes());
+        block[10..14].copy_from_slice(&frame_count.to_le_bytes());
+        block[15] = (i + 1) as u8;
+
+        let stream = aes128_encrypt(key, block);
+        output.extend(chunk.iter().zip(stream).map(|(p, s)| p ^ s));
+    }
+
+    output
+}
+
+/// Decrypts an FRMPayload. The key is the network session key for port 0, and the application
+/// session key otherwise.
+pub fn decrypt_frm_payload(
+    key: &Key,
+    direction: Direction,
+    device_address: u32,
+    frame_count: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    encrypt_frm_payload(key, direction, device_address, frame_count, payload)
+}
+
+pub(crate) fn aes128_cmac(key: &Key, data: &[u8]) -> [u8; BLOCK_LENGTH] {
+    let mut mac = <Cmac<Aes128> as Mac>::new_from_slice(key.digits()).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn aes128_encrypt(key: &Key, block: [u8; BLOCK_LENGTH]) -> [u8; BLOCK_LENGTH] {
+    let cipher = Aes128::new(GenericArray::from_slice(key.digits()));
+    let mut block = GenericArray::from(block);
+    cipher.encrypt_block(&mut block);
+    block.into()
+}
+
+fn aes128_decrypt(key: &Key, block: [u8; BLOCK_LENGTH]) -> [u8; BLOCK_LENGTH] {
+    let cipher = Aes128::new(GenericArray::from_slice(key.digits()));
+    let mut block = GenericArray::from(block);
+    cipher.decrypt_block(&mut block);
+    block.into()
+}
+
+fn truncate_mic(cmac: [u8; BLOCK_LENGTH]) -> [u8; MIC_LENGTH] {
+    cmac[..MIC_LENGTH].try_into().unwrap()
+}
+
+fn split_mic(phy_payload: &[u8]) -> Result<(&[u8], &[u8]), MtcapError> {
+    if phy_payload.len() < MIC_LENGTH {
+        return Err(MtcapError::Other(format!(
+            "PHYPayload of length {} is too short to contain a MIC",
+            phy_payload.len()
+        )));
+    }
+    Ok(phy_payload.split_at(phy_payload.len() - MIC_LENGTH))
+}
+
+fn check_mic(mic: &[u8], expected: &[u8; MIC_LENGTH]) -> Result<(), MtcapError> {
+    if mic == expected {
+        Ok(())
+    } else {
+        Err(MtcapError::InvalidMic)
+    }
+}
+
+#[cfg(test)]
+#[path = "./test_crypto.rs"]
+mod test_crypto;
@@ -1,15 +1,20 @@
-use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::os::windows::process::CommandExt as _;
-use std::process::{Command, Output};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
 
 use crate::result::MtcapError;
 
-pub const CREATE_NO_WINDOW: u32 = 0x08000000;
+pub(crate) const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+const CONNECT_TIMEOUT_SECONDS: &str = "10";
 
 pub fn get(url: String) -> Result<String, MtcapError> {
     let response = Command::new("curl")
         .arg("-k")
+        .arg("-g")
+        .arg("--connect-timeout")
+        .arg(CONNECT_TIMEOUT_SECONDS)
         .arg(url)
         .creation_flags(CREATE_NO_WINDOW)
         .output()?;
@@ -22,6 +27,7 @@ pub fn get(url: String) -> Result<String, MtcapError> {
 pub fn post(url: String) -> Result<(), MtcapError> {
     let response = Command::new("curl")
         .arg("-k")
+        .arg("-g")
         .arg(url)
         .arg("-X")
         .arg("POST")
@@ -35,30 +41,92 @@ pub fn post(url: String) -> Result<(), MtcapError> {
     Ok(())
 }
 
+pub fn post_json(url: String, json: json::JsonValue) -> Result<(), MtcapError> {
+    send_json(url, "POST", json)?;
+
+    Ok(())
+}
+
+pub fn post_json_with_response(url: String, json: json::JsonValue) -> Result<String, MtcapError> {
+    send_json(url, "POST", json)
+}
+
 pub fn put(url: String, json: json::JsonValue) -> Result<(), MtcapError> {
-    const FILE_NAME: &str = "temporary_file_to_transmit_data_through_curl.json";
+    send_json(url, "PUT", json)?;
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(FILE_NAME)?;
-    write!(&mut file, "{}", json::stringify(json))?;
+    Ok(())
+}
 
+fn send_json(url: String, method: &str, json: json::JsonValue) -> Result<String, MtcapError> {
+    let response = output_with_input(
+        Command::new("curl")
+            .arg("-k")
+            .arg("-g")
+            .arg(url)
+            .arg("-X")
+            .arg(method)
+            .arg("--data-binary")
+            .arg("@-")
+            .arg("-H")
+            .arg("Content-Type: application/json"),
+        json::stringify(json).as_bytes(),
+    )?;
+
+    response_analyse(&response)?;
+
+    Ok(String::from_utf8_lossy(&response.stdout).to_string())
+}
+
+/// Gets a binary response, such as a file download.
+pub fn download(url: String) -> Result<Vec<u8>, MtcapError> {
     let response = Command::new("curl")
         .arg("-k")
+        .arg("-g")
+        .arg("--fail")
+        .arg("--connect-timeout")
+        .arg(CONNECT_TIMEOUT_SECONDS)
         .arg(url)
-        .arg("-X")
-        .arg("PUT")
-        .arg("-d")
-        .arg(format!("@{}", FILE_NAME))
-        .arg("-H")
-        .arg("Content-Type: application/json")
         .creation_flags(CREATE_NO_WINDOW)
-        .output();
+        .output()?;
+
+    if !response.status.success() {
+        return Err(MtcapError::Other(
+            String::from_utf8_lossy(&response.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(response.stdout)
+}
+
+/// Uploads a file as the `file` field of a multipart form.
+pub fn upload(url: String, path: &Path) -> Result<(), MtcapError> {
+    let response = Command::new("curl")
+        .arg("-k")
+        .arg("-g")
+        .arg(url)
+        .arg("-F")
+        .arg(format!("file=@{}", path.display()))
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()?;
 
-    let _ = fs::remove_file(FILE_NAME);
+    response_analyse(&response)?;
 
-    response_analyse(&response?)?;
+    Ok(())
+}
+
+/// Uploads `bytes` as the `file` field of a multipart form, sending them through curl's stdin.
+pub fn upload_bytes(url: String, bytes: &[u8]) -> Result<(), MtcapError> {
+    let response = output_with_input(
+        Command::new("curl")
+            .arg("-k")
+            .arg("-g")
+            .arg(url)
+            .arg("-F")
+            .arg("file=@-"),
+        bytes,
+    )?;
+
+    response_analyse(&response)?;
 
     Ok(())
 }
@@ -66,6 +134,7 @@ pub fn put(url: String, json: json::JsonValue) -> Result<(), MtcapError> {
 pub fn delete(url: String) -> Result<(), MtcapError> {
     let response = Command::new("curl")
         .arg("-k")
+        .arg("-g")
         .arg(url)
         .arg("-X")
         .arg("DELETE")
@@ -77,6 +146,41 @@ pub fn delete(url: String) -> Result<(), MtcapError> {
     Ok(())
 }
 
+/// Deletes, sending the token as a cookie rather than in the URL, as some firmware requires.
+pub fn delete_with_cookie(url: String, token: &str) -> Result<(), MtcapError> {
+    let response = Command::new("curl")
+        .arg("-k")
+        .arg("-g")
+        .arg(url)
+        .arg("-X")
+        .arg("DELETE")
+        .arg("--cookie")
+        .arg(format!("token={token}"))
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()?;
+
+    response_analyse(&response)?;
+
+    Ok(())
+}
+
+/// Runs `command` with `input` on its stdin, so that concurrent requests share no files.
+fn output_with_input(command: &mut Command, input: &[u8]) -> std::io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()?;
+
+    // curl reads the whole body before sending, so stdin is closed before its output is read.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+
+    child.wait_with_output()
+}
+
 fn response_analyse(output: &Output) -> Result<(), MtcapError> {
     let json = json::parse(&String::from_utf8_lossy(&output.stdout))?;
     let status = json["status"].to_string();
@@ -89,3 +193,7 @@ fn response_analyse(output: &Output) -> Result<(), MtcapError> {
         Err(MtcapError::Other(format!("{:?}", output)))
     }
 }
+
+#[cfg(test)]
+#[path = "./test_curl.rs"]
+mod test_curl;
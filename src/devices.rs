@@ -1,17 +1,33 @@
+use std::collections::HashSet;
 use std::fmt;
+use std::fs;
 use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use strum_macros::Display;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
 
-use crate::credentials::{get_url, save_apply, Token};
+use strum_macros::{Display, EnumString};
+
+use crate::credentials::{
+    get_url, get_url_with_query, get_url_without_token, save_apply_and_wait, Token,
+};
+use crate::crypto::aes128_cmac;
 use crate::curl;
+use crate::region::RegionalParameters;
 use crate::result::MtcapError;
 
 const EUI_LENGTH: usize = 8;
 
 const KEY_LENGTH: usize = 16;
 
+pub(crate) const DEVICE_PROFILE_ID_PREFIX: &str = "LW102-OTA-";
+
+pub(crate) const NETWORK_PROFILE_ID_PREFIX: &str = "DEFAULT-CLASS-";
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Clone)]
 pub struct Device {
     device_eui: Eui,
     join_eui: Eui,
@@ -39,9 +55,13 @@ impl Device {
             network_profile,
         }
     }
+
+    pub const fn device_eui(&self) -> &Eui {
+        &self.device_eui
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Eui {
     digits: [u8; EUI_LENGTH],
 }
@@ -76,8 +96,13 @@ impl Eui {
     pub const fn new(eui: [u8; EUI_LENGTH]) -> Self {
         Self { digits: eui }
     }
+
+    pub const fn digits(&self) -> &[u8; EUI_LENGTH] {
+        &self.digits
+    }
 }
 
+#[derive(Clone)]
 pub struct Key {
     digits: [u8; KEY_LENGTH],
 }
@@ -122,6 +147,25 @@ impl Key {
         Self { digits: key }
     }
 
+    /// Generates a random key using the operating system's random number generator.
+    pub fn generate() -> Result<Self, MtcapError> {
+        let mut digits = [0; KEY_LENGTH];
+        getrandom::getrandom(&mut digits).map_err(|e| MtcapError::Io(e.into()))?;
+        Ok(Self::new(digits))
+    }
+
+    /// Derives a per-device key from a master key and the device EUI, as AES-CMAC(master, DevEUI).
+    ///
+    /// The same master key and device EUI always give the same key, so the key does not need to be
+    /// stored.
+    pub fn derive(master: &Key, device_eui: &Eui) -> Self {
+        Self::new(aes128_cmac(master, &device_eui.digits))
+    }
+
+    pub const fn digits(&self) -> &[u8; KEY_LENGTH] {
+        &self.digits
+    }
+
     fn to_string_no_spaces(&self) -> String {
         let mut output = self.to_string();
         output.retain(|c| c.is_ascii_hexdigit());
@@ -183,29 +227,57 @@ fn string_unpadded_to_vec_u8(input: &str, output_length: usize) -> io::Result<Ve
     }
 }
 
-#[derive(Display)]
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
 pub enum Class {
     A,
     B,
     C,
 }
 
-#[derive(Display)]
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
 pub enum DeviceProfile {
     #[strum(serialize = "AS923")]
     As923,
+    #[strum(serialize = "AS923-2")]
+    As923_2,
+    #[strum(serialize = "AS923-3")]
+    As923_3,
+    #[strum(serialize = "AS923-4")]
+    As923_4,
     #[strum(serialize = "AU915")]
     Au915,
+    #[strum(serialize = "CN470")]
+    Cn470,
+    #[strum(serialize = "CN779")]
+    Cn779,
+    #[strum(serialize = "EU433")]
+    Eu433,
     #[strum(serialize = "EU868")]
     Eu868,
     #[strum(serialize = "IN865")]
     In865,
     #[strum(serialize = "KR920")]
     Kr920,
+    #[strum(serialize = "RU864")]
+    Ru864,
     #[strum(serialize = "US915")]
     Us915,
 }
 
+impl DeviceProfile {
+    pub fn regional_parameters(&self) -> RegionalParameters {
+        RegionalParameters::new(self)
+    }
+
+    pub(crate) fn from_id(device_profile_id: &str) -> Option<Self> {
+        device_profile_id
+            .strip_prefix(DEVICE_PROFILE_ID_PREFIX)?
+            .parse()
+            .ok()
+    }
+}
+
+/// Counts the allowlist entries. See [`count`] for the devices in the gateway's device list.
 pub fn get_count(token: &Token) -> Result<usize, MtcapError> {
     let gateway_response = curl::get(get_url(token, "loraNetwork/whitelist"))?;
     let devices_json = &json::parse(&gateway_response)?["result"]["devices"];
@@ -224,27 +296,32 @@ pub fn enable(token: &Token, devices: &[Device]) -> Result<(), MtcapError> {
 
     curl::put(get_url(token, "loraNetwork/whitelist"), devices_json)?;
 
-    save_apply(token)?;
+    save_apply_and_wait(token)?;
 
     Ok(())
 }
 
 pub fn clear(token: &Token) -> Result<(), MtcapError> {
-    enable(token, &[])?;
-
-    let gateway_response = curl::get(get_url(token, "lora/devices"))?;
-    let devices_json = json::parse(&gateway_response)?["result"].clone();
-    let mut index = 0;
-    while !devices_json[index].is_null() {
-        let device_eui = devices_json[index]["deveui"].to_string();
-        curl::delete(get_url(token, format!("lora/devices/{device_eui}")))?;
-
-        index += 1;
-    }
+    clear_with(token, ClearStrategy::default(), |_| {})
+}
 
-    save_apply(token)?;
+pub fn clear_with<F: FnMut(&Progress)>(
+    token: &Token,
+    strategy: ClearStrategy,
+    progress: F,
+) -> Result<(), MtcapError> {
+    BulkRemoval::all(token, strategy)?.run(token, progress)
+}
 
-    Ok(())
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ClearStrategy {
+    /// Empties the allowlist, then deletes each device.
+    #[default]
+    Allowlist,
+    /// Deletes each device without touching the allowlist, for firmware on which emptying the
+    /// allowlist does not remove the devices. The token is sent as a cookie, and a failed delete
+    /// is reported through [`Progress::error`] without stopping the others.
+    OneByOne,
 }
 
 pub fn add(token: &Token, devices: &[Device]) -> Result<(), MtcapError> {
@@ -272,7 +349,7 @@ pub fn add(token: &Token, devices: &[Device]) -> Result<(), MtcapError> {
 
     curl::put(get_url(token, "loraNetwork/whitelist"), devices_json)?;
 
-    save_apply(token)?;
+    save_apply_and_wait(token)?;
 
     Ok(())
 }
@@ -282,13 +359,353 @@ pub fn remove(token: &Token, devices: &[Eui]) -> Result<(), MtcapError> {
         return Ok(());
     }
 
+    BulkRemoval::new(token, devices)?.run(token, |_| {})
+}
+
+/// Progress of a [`BulkRemoval`], reported after each device is removed or, with
+/// [`ClearStrategy::OneByOne`], fails to be removed.
+pub struct Progress<'a> {
+    /// The device EUI as the gateway lists it, which may not parse as an [`Eui`].
+    pub device_eui: &'a str,
+    pub removed: usize,
+    pub total: usize,
+    /// Why the device could not be removed.
+    pub error: Option<&'a MtcapError>,
+}
+
+/// A removal of many devices, one request per device.
+///
+/// Devices are deleted by the device EUI as the gateway lists it, so that entries whose EUI does
+/// not parse are removed too.
+///
+/// If [`BulkRemoval::run`] fails partway, the removal keeps track of which devices are still
+/// pending, so `run` can be called again to resume. To resume in another process, save
+/// [`BulkRemoval::pending`] and pass the parsed EUIs to [`BulkRemoval::new`].
+#[derive(Clone, Debug)]
+pub struct BulkRemoval {
+    allowlist_update: AllowlistUpdate,
+    allowlist_done: bool,
+    pending: Vec<String>,
+    removed: Vec<String>,
+    failed: Vec<String>,
+    snapshot_path: Option<PathBuf>,
+}
+
+impl BulkRemoval {
+    /// Prepares removal of `devices` from the allowlist and from the gateway's device list.
+    pub fn new(token: &Token, devices: &[Eui]) -> Result<Self, MtcapError> {
+        let mut pending = Vec::new();
+        for device_json in get_devices_json(token)?.members() {
+            let device_eui = device_json["deveui"].to_string();
+            if Eui::from_str(&device_eui).is_ok_and(|device_eui| devices.contains(&device_eui)) {
+                pending.push(device_eui);
+            }
+        }
+
+        Ok(Self {
+            allowlist_update: AllowlistUpdate::Remove(devices.to_vec()),
+            allowlist_done: false,
+            pending,
+            removed: Vec::new(),
+            failed: Vec::new(),
+            snapshot_path: None,
+        })
+    }
+
+    /// Prepares removal of every device.
+    pub fn all(token: &Token, strategy: ClearStrategy) -> Result<Self, MtcapError> {
+        let mut pending = Vec::new();
+        for device_json in get_devices_json(token)?.members() {
+            pending.push(device_json["deveui"].to_string());
+        }
+
+        Ok(Self {
+            allowlist_update: match strategy {
+                ClearStrategy::Allowlist => AllowlistUpdate::Clear,
+                ClearStrategy::OneByOne => AllowlistUpdate::None,
+            },
+            allowlist_done: false,
+            pending,
+            removed: Vec::new(),
+            failed: Vec::new(),
+            snapshot_path: None,
+        })
+    }
+
+    pub fn pending(&self) -> &[String] {
+        &self.pending
+    }
+
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// The devices which [`ClearStrategy::OneByOne`] could not remove. The next
+    /// [`BulkRemoval::run`] retries them.
+    pub fn failed(&self) -> &[String] {
+        &self.failed
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.allowlist_done && self.pending.is_empty() && self.failed.is_empty()
+    }
+
+    /// Where the snapshot taken before the removal was saved, if the token has a snapshot
+    /// directory.
+    pub fn snapshot_path(&self) -> Option<&Path> {
+        self.snapshot_path.as_deref()
+    }
+
+    /// Removes the pending devices, calling `progress` after each one, then saves and applies if
+    /// the allowlist changed.
+    pub fn run<F: FnMut(&Progress)>(
+        &mut self,
+        token: &Token,
+        mut progress: F,
+    ) -> Result<(), MtcapError> {
+        if let Some(snapshot_dir) = token.snapshot_dir() {
+            if self.snapshot_path.is_none() {
+                self.snapshot_path =
+                    Some(DeviceSnapshot::take(token)?.save_in(token, snapshot_dir)?);
+            }
+        }
+
+        if !self.allowlist_done {
+            match &self.allowlist_update {
+                AllowlistUpdate::None => {}
+                AllowlistUpdate::Clear => enable(token, &[])?,
+                AllowlistUpdate::Remove(devices) => remove_from_allowlist(token, devices)?,
+            }
+            self.allowlist_done = true;
+        }
+
+        let one_by_one = self.allowlist_update == AllowlistUpdate::None;
+        self.pending.append(&mut self.failed);
+        let total = self.pending.len() + self.removed.len();
+        while let Some(device_eui) = self.pending.first() {
+            let api = format!("lora/devices/{device_eui}");
+            let result = if one_by_one {
+                curl::delete_with_cookie(get_url_without_token(token, api), token.token())
+            } else {
+                curl::delete(get_url(token, api))
+            };
+
+            let device_eui = self.pending.remove(0);
+            match result {
+                Ok(()) => {
+                    self.removed.push(device_eui);
+                    progress(&Progress {
+                        device_eui: self.removed.last().unwrap(),
+                        removed: self.removed.len(),
+                        total,
+                        error: None,
+                    });
+                }
+                Err(e) if one_by_one => {
+                    progress(&Progress {
+                        device_eui: &device_eui,
+                        removed: self.removed.len(),
+                        total,
+                        error: Some(&e),
+                    });
+                    self.failed.push(device_eui);
+                }
+                Err(e) => {
+                    self.pending.insert(0, device_eui);
+                    return Err(e);
+                }
+            }
+        }
+
+        if !self.failed.is_empty() {
+            return Err(MtcapError::Other(format!(
+                "{} of {total} devices could not be removed",
+                self.failed.len()
+            )));
+        }
+
+        if self.allowlist_update != AllowlistUpdate::None {
+            save_apply_and_wait(token)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The allowlist and `lora/devices` list of a gateway, saved before a removal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceSnapshot {
+    allowlist: json::JsonValue,
+    devices: json::JsonValue,
+}
+
+impl DeviceSnapshot {
+    pub fn take(token: &Token) -> Result<Self, MtcapError> {
+        Ok(Self {
+            allowlist: get_allowlist_json(token)?,
+            devices: get_devices_json(token)?,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MtcapError> {
+        let json = json::object! {
+            allowlist: self.allowlist.clone(),
+            devices: self.devices.clone(),
+        };
+        fs::write(path, json::stringify_pretty(json, 2))?;
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, MtcapError> {
+        let json = json::parse(&fs::read_to_string(path)?)?;
+
+        Ok(Self {
+            allowlist: json["allowlist"].clone(),
+            devices: json["devices"].clone(),
+        })
+    }
+
+    /// The device EUIs in the saved allowlist.
+    pub fn allowlist(&self) -> Result<Vec<Eui>, MtcapError> {
+        euis(&self.allowlist["devices"])
+    }
+
+    /// The device EUIs in the saved device list.
+    pub fn devices(&self) -> Result<Vec<Eui>, MtcapError> {
+        euis(&self.devices)
+    }
+
+    /// Saves the snapshot in `dir`, named after the gateway's host and port and the current time.
+    fn save_in(&self, token: &Token, dir: &Path) -> Result<PathBuf, MtcapError> {
+        let address = token.address();
+        let gateway = match address.port() {
+            Some(port) => format!("{}-{port}", address.host()),
+            None => address.host().to_string(),
+        };
+        let gateway: String = gateway
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(format!(
+            "{gateway}-{}.json",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+
+        fs::create_dir_all(dir)?;
+        self.save(&path)?;
+
+        Ok(path)
+    }
+}
+
+/// The devices which [`rollback`] re-created, and those it could not.
+#[derive(Debug, Default)]
+pub struct Rollback {
+    sessions_created: Vec<String>,
+    sessions_failed: Vec<(String, MtcapError)>,
+}
+
+impl Rollback {
+    pub fn sessions_created(&self) -> &[String] {
+        &self.sessions_created
+    }
+
+    /// The devices which could not be re-created, including those whose EUI does not parse. These
+    /// devices will need to rejoin.
+    pub fn sessions_failed(&self) -> &[(String, MtcapError)] {
+        &self.sessions_failed
+    }
+}
+
+/// Restores the allowlist from `snapshot`, re-creates the devices missing from the gateway with
+/// their sessions, then saves and applies. A device which cannot be re-created does not stop the
+/// others.
+pub fn rollback(token: &Token, snapshot: &DeviceSnapshot) -> Result<Rollback, MtcapError> {
+    put_allowlist_json(token, snapshot.allowlist.clone())?;
+
+    let existing = get_devices_json(token)?
+        .members()
+        .map(|device_json| device_json["deveui"].to_string())
+        .collect::<HashSet<_>>();
+    let mut rollback = Rollback::default();
+    for device_json in snapshot.devices.members() {
+        let device_eui = device_json["deveui"].to_string();
+        if existing.contains(&device_eui) {
+            continue;
+        }
+
+        let result = match Eui::from_str(&device_eui) {
+            Ok(_) => create_session(token, device_json),
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(()) => rollback.sessions_created.push(device_eui),
+            Err(e) => rollback.sessions_failed.push((device_eui, e)),
+        }
+    }
+
+    save_apply_and_wait(token)?;
+
+    Ok(rollback)
+}
+
+/// Gets the allowlist as the gateway returns it, with `enabled`, `devices` and any other fields,
+/// for snapshots to write back unchanged with [`put_allowlist_json`].
+pub(crate) fn get_allowlist_json(token: &Token) -> Result<json::JsonValue, MtcapError> {
+    let gateway_response = curl::get(get_url(token, "loraNetwork/whitelist"))?;
+
+    Ok(json::parse(&gateway_response)?["result"].clone())
+}
+
+pub(crate) fn put_allowlist_json(
+    token: &Token,
+    allowlist_json: json::JsonValue,
+) -> Result<(), MtcapError> {
+    curl::put(get_url(token, "loraNetwork/whitelist"), allowlist_json)
+}
+
+/// Creates a device with its session from an entry of the `lora/devices` list, as taken from this
+/// or another gateway, so that it need not rejoin.
+pub(crate) fn create_session(
+    token: &Token,
+    device_json: &json::JsonValue,
+) -> Result<(), MtcapError> {
+    let mut session_json = device_json.clone();
+    session_json.remove("created_at");
+    session_json.remove("last_seen");
+
+    curl::post_json(get_url(token, "lora/devices"), session_json)
+}
+
+pub(crate) fn euis(devices_json: &json::JsonValue) -> Result<Vec<Eui>, MtcapError> {
+    devices_json
+        .members()
+        .map(|device_json| Ok(Eui::from_str(&device_json["deveui"].to_string())?))
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AllowlistUpdate {
+    None,
+    Clear,
+    Remove(Vec<Eui>),
+}
+
+fn remove_from_allowlist(token: &Token, devices: &[Eui]) -> Result<(), MtcapError> {
     let gateway_response = curl::get(get_url(token, "loraNetwork/whitelist"))?;
     let mut allowlist_json = json::parse(&gateway_response)?["result"].clone();
     let mut index = 0;
     while !allowlist_json["devices"][index].is_null() {
         let device_eui_existing =
-            Eui::from_str(&allowlist_json["devices"][index]["deveui"].to_string())?;
-        if devices.contains(&device_eui_existing) {
+            Eui::from_str(&allowlist_json["devices"][index]["deveui"].to_string());
+        if device_eui_existing.is_ok_and(|device_eui| devices.contains(&device_eui)) {
             allowlist_json["devices"].array_remove(index);
         } else {
             index += 1;
@@ -297,61 +714,303 @@ pub fn remove(token: &Token, devices: &[Eui]) -> Result<(), MtcapError> {
 
     curl::put(get_url(token, "loraNetwork/whitelist"), allowlist_json)?;
 
-    let gateway_response = curl::get(get_url(token, "lora/devices"))?;
-    let devices_json = json::parse(&gateway_response)?["result"].clone();
-    let mut index = 0;
-    while !devices_json[index].is_null() {
-        let device_eui = devices_json[index]["deveui"].to_string();
-        let device_eui_existing = Eui::from_str(&device_eui)?;
-        if devices.contains(&device_eui_existing) {
-            curl::delete(get_url(token, format!("lora/devices/{device_eui}")))?;
+    Ok(())
+}
+
+/// Gets `lora/devices` a page at a time.
+pub(crate) fn get_devices_json(token: &Token) -> Result<json::JsonValue, MtcapError> {
+    let mut devices_json = json::JsonValue::new_array();
+    let mut device_euis = HashSet::new();
+
+    loop {
+        let gateway_response = curl::get(get_url_with_query(
+            token,
+            "lora/devices",
+            &format!("limit={PAGE_SIZE}&offset={}", devices_json.len()),
+        ))?;
+        let page_json = &json::parse(&gateway_response)?["result"];
+
+        if !merge_page(&mut devices_json, &mut device_euis, page_json)? {
+            return Ok(devices_json);
         }
+    }
+}
 
-        index += 1;
+/// Adds the devices in `page_json` not seen before, and returns whether there may be another page.
+/// Firmware which ignores paging returns every device in the first page, and the repeated devices
+/// in the next page end the loop.
+fn merge_page(
+    devices_json: &mut json::JsonValue,
+    device_euis: &mut HashSet<String>,
+    page_json: &json::JsonValue,
+) -> Result<bool, MtcapError> {
+    let mut new_devices = 0;
+    for device_json in page_json.members() {
+        if device_euis.insert(device_json["deveui"].to_string()) {
+            devices_json.push(device_json.clone())?;
+            new_devices += 1;
+        }
     }
 
-    save_apply(token)?;
+    Ok(page_json.len() >= PAGE_SIZE && new_devices > 0)
+}
 
-    Ok(())
+/// Removes devices last seen, or if never seen created, before `older_than`.
+pub fn remove_old(token: &Token, older_than: NaiveDate) -> Result<Selection, MtcapError> {
+    let filter = Filter::new().last_seen_before(older_than.and_time(Default::default()).and_utc());
+
+    remove_matching(token, &filter)
 }
 
-pub fn remove_old(token: &Token, older_than: chrono::NaiveDate) -> Result<(), MtcapError> {
-    let mut devices_to_remove = Vec::new();
-
-    let gateway_response = curl::get(get_url(token, "lora/devices"))?;
-    let devices_json = json::parse(&gateway_response)?["result"].clone();
-    if let json::JsonValue::Array(devices_array) = devices_json {
-        for device in devices_array {
-            let device_date = if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(
-                &device["last_seen"].to_string(),
-                "%Y-%m-%dT%H:%M:%SZ",
-            ) {
-                dt.date()
-            } else if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(
-                &device["created_at"].to_string(),
-                "%Y-%m-%dT%H:%M:%SZ",
-            ) {
-                dt.date()
-            } else {
-                continue;
-            };
-            if device_date < older_than {
-                devices_to_remove.push(Eui::from_str(&device["deveui"].to_string())?);
+/// A device as listed by the gateway in `lora/devices`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceRecord {
+    device_eui: Eui,
+    class: Option<Class>,
+    device_profile: Option<DeviceProfile>,
+    created_at: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+impl DeviceRecord {
+    pub fn device_eui(&self) -> &Eui {
+        &self.device_eui
+    }
+
+    pub fn class(&self) -> Option<Class> {
+        self.class
+    }
+
+    pub fn device_profile(&self) -> Option<DeviceProfile> {
+        self.device_profile
+    }
+
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    pub fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.last_seen
+    }
+
+    pub fn joined(&self) -> bool {
+        self.last_seen.is_some()
+    }
+
+    fn from_json(json: &json::JsonValue) -> Result<Self, String> {
+        Ok(Self {
+            device_eui: Eui::from_str(&json["deveui"].to_string()).map_err(|e| e.to_string())?,
+            class: json["class"].as_str().and_then(|class| class.parse().ok()),
+            device_profile: json["device_profile_id"]
+                .as_str()
+                .and_then(DeviceProfile::from_id),
+            created_at: parse_timestamp(&json["created_at"])?,
+            last_seen: parse_timestamp(&json["last_seen"])?,
+        })
+    }
+}
+
+/// A device which the gateway listed but which could not be interpreted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnparseableDevice {
+    json: String,
+    error: String,
+}
+
+impl UnparseableDevice {
+    pub fn json(&self) -> &str {
+        &self.json
+    }
+
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+}
+
+/// The devices matching a [`Filter`], and those which could not be checked against it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    devices: Vec<DeviceRecord>,
+    unparseable: Vec<UnparseableDevice>,
+}
+
+impl Selection {
+    pub fn devices(&self) -> &[DeviceRecord] {
+        &self.devices
+    }
+
+    pub fn unparseable(&self) -> &[UnparseableDevice] {
+        &self.unparseable
+    }
+
+    fn from_json(devices_json: &json::JsonValue, filter: &Filter) -> Self {
+        let mut selection = Self::default();
+        for device_json in devices_json.members() {
+            match DeviceRecord::from_json(device_json) {
+                Ok(device) => {
+                    if filter.matches(&device) {
+                        selection.devices.push(device);
+                    }
+                }
+                Err(error) => selection.unparseable.push(UnparseableDevice {
+                    json: device_json.dump(),
+                    error,
+                }),
             }
         }
+        selection
+    }
+}
+
+/// A set of conditions on devices, all of which must hold for a device to match.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug)]
+enum Condition {
+    LastSeenBefore(DateTime<Utc>),
+    LastSeenAfter(DateTime<Utc>),
+    NeverJoined,
+    Class(Class),
+    DeviceProfile(DeviceProfile),
+    EuiPrefix(Vec<u8>),
+    EuiRange(Eui, Eui),
+}
+
+impl Filter {
+    /// Creates a filter which matches every device.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches devices last seen, or if never seen created, before `time`.
+    pub fn last_seen_before(mut self, time: DateTime<Utc>) -> Self {
+        self.conditions.push(Condition::LastSeenBefore(time));
+        self
+    }
+
+    /// Matches devices last seen after `time`.
+    pub fn last_seen_after(mut self, time: DateTime<Utc>) -> Self {
+        self.conditions.push(Condition::LastSeenAfter(time));
+        self
     }
 
-    remove(token, &devices_to_remove)
+    /// Matches devices last seen, or if never seen created, more than `age` ago.
+    pub fn last_seen_older_than(self, age: TimeDelta) -> Self {
+        self.last_seen_before(Utc::now() - age)
+    }
+
+    /// Matches devices last seen less than `age` ago.
+    pub fn last_seen_within(self, age: TimeDelta) -> Self {
+        self.last_seen_after(Utc::now() - age)
+    }
+
+    pub fn never_joined(mut self) -> Self {
+        self.conditions.push(Condition::NeverJoined);
+        self
+    }
+
+    pub fn class(mut self, class: Class) -> Self {
+        self.conditions.push(Condition::Class(class));
+        self
+    }
+
+    pub fn device_profile(mut self, device_profile: DeviceProfile) -> Self {
+        self.conditions
+            .push(Condition::DeviceProfile(device_profile));
+        self
+    }
+
+    /// Matches devices whose EUI starts with the bytes of `prefix`.
+    pub fn eui_prefix(mut self, prefix: &[u8]) -> Self {
+        self.conditions.push(Condition::EuiPrefix(prefix.to_vec()));
+        self
+    }
+
+    /// Matches devices whose EUI is from `first` to `last` inclusive.
+    pub fn eui_range(mut self, first: Eui, last: Eui) -> Self {
+        self.conditions.push(Condition::EuiRange(first, last));
+        self
+    }
+
+    pub fn matches(&self, device: &DeviceRecord) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            Condition::LastSeenBefore(time) => device
+                .last_seen
+                .or(device.created_at)
+                .is_some_and(|last_seen| last_seen < *time),
+            Condition::LastSeenAfter(time) => {
+                device.last_seen.is_some_and(|last_seen| last_seen > *time)
+            }
+            Condition::NeverJoined => !device.joined(),
+            Condition::Class(class) => device.class == Some(*class),
+            Condition::DeviceProfile(device_profile) => {
+                device.device_profile == Some(*device_profile)
+            }
+            Condition::EuiPrefix(prefix) => device.device_eui.digits.starts_with(prefix),
+            Condition::EuiRange(first, last) => {
+                *first <= device.device_eui && device.device_eui <= *last
+            }
+        })
+    }
+}
+
+pub fn list(token: &Token, filter: &Filter) -> Result<Selection, MtcapError> {
+    let devices_json = get_devices_json(token)?;
+
+    Ok(Selection::from_json(&devices_json, filter))
+}
+
+/// Counts the devices in the gateway's device list, `lora/devices`, matching `filter`, along with
+/// the devices which could not be checked. See [`get_count`] for the allowlist entries.
+pub fn count(
+    token: &Token,
+    filter: &Filter,
+) -> Result<(usize, Vec<UnparseableDevice>), MtcapError> {
+    let selection = list(token, filter)?;
+
+    Ok((selection.devices.len(), selection.unparseable))
+}
+
+/// Removes the devices matching `filter`, returning them along with the devices which could not be
+/// checked and so were kept.
+pub fn remove_matching(token: &Token, filter: &Filter) -> Result<Selection, MtcapError> {
+    let selection = list(token, filter)?;
+
+    let devices_to_remove: Vec<Eui> = selection
+        .devices
+        .iter()
+        .map(|device| device.device_eui.clone())
+        .collect();
+    remove(token, &devices_to_remove)?;
+
+    Ok(selection)
+}
+
+pub(crate) fn parse_timestamp(json: &json::JsonValue) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(timestamp) = json.as_str().filter(|timestamp| !timestamp.is_empty()) else {
+        return Ok(None);
+    };
+
+    if let Ok(dt) = NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%SZ") {
+        Ok(Some(dt.and_utc()))
+    } else {
+        DateTime::parse_from_rfc3339(timestamp)
+            .map(|dt| Some(dt.to_utc()))
+            .map_err(|e| format!("{timestamp}: {e}"))
+    }
 }
 
-fn create_json(device: &Device) -> json::JsonValue {
+pub(crate) fn create_json(device: &Device) -> json::JsonValue {
     json::object! {
         deveui: device.device_eui.to_string(),
         appeui: device.join_eui.to_string(),
         appkey: device.application_key.to_string_no_spaces(),
         class: device.class.to_string(),
-        device_profile_id: format!("LW102-OTA-{}", device.device_profile),
-        network_profile_id: format!("DEFAULT-CLASS-{}", device.network_profile),
+        device_profile_id: format!("{DEVICE_PROFILE_ID_PREFIX}{}", device.device_profile),
+        network_profile_id: format!("{NETWORK_PROFILE_ID_PREFIX}{}", device.network_profile),
     }
 }
 
@@ -360,8 +1019,10 @@ fn update_json(device: &Device, json: &mut json::JsonValue) -> Result<(), MtcapE
     json["appeui"] = device.join_eui.to_string().into();
     json["appkey"] = device.application_key.to_string_no_spaces().into();
     json["class"] = device.class.to_string().into();
-    json["device_profile_id"] = format!("LW102-OTA-{}", device.device_profile).into();
-    json["network_profile_id"] = format!("DEFAULT-CLASS-{}", device.network_profile).into();
+    json["device_profile_id"] =
+        format!("{DEVICE_PROFILE_ID_PREFIX}{}", device.device_profile).into();
+    json["network_profile_id"] =
+        format!("{NETWORK_PROFILE_ID_PREFIX}{}", device.network_profile).into();
 
     Ok(())
 }
@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::credentials::{get_url, Gateway, Token};
+use crate::curl;
+use crate::result::MtcapError;
+use crate::system::{info, is_down, poll_until, wait_for_login, POLL_INTERVAL};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpgradeState {
+    Idle,
+    Uploaded,
+    Installing,
+    Done,
+    Failed,
+}
+
+/// The progress of a firmware upgrade, as reported by the gateway.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpgradeStatus {
+    state: UpgradeState,
+    percent: Option<u8>,
+    message: String,
+}
+
+impl UpgradeStatus {
+    pub fn state(&self) -> UpgradeState {
+        self.state
+    }
+
+    /// The progress of the current step, where the gateway reports one.
+    pub fn percent(&self) -> Option<u8> {
+        self.percent
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn from_json(json: &json::JsonValue) -> Result<Self, MtcapError> {
+        let state = match json["state"].as_str().unwrap_or_default() {
+            "" | "idle" => UpgradeState::Idle,
+            "uploaded" | "ready" => UpgradeState::Uploaded,
+            "installing" | "upgrading" | "in progress" => UpgradeState::Installing,
+            "done" | "complete" | "success" => UpgradeState::Done,
+            "failed" | "error" => UpgradeState::Failed,
+            other => {
+                return Err(MtcapError::Other(format!(
+                    "Unknown firmware upgrade state {other}"
+                )))
+            }
+        };
+
+        Ok(Self {
+            state,
+            percent: json["progress"].as_u8().filter(|percent| *percent <= 100),
+            message: json["message"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// The SHA-256 of a file, as lowercase hex.
+pub fn sha256(path: &Path) -> Result<String, MtcapError> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Checks a file against a SHA-256 given as hex.
+pub fn verify_checksum(path: &Path, expected_sha256: &str) -> Result<(), MtcapError> {
+    let actual = sha256(path)?;
+
+    if actual.eq_ignore_ascii_case(expected_sha256.trim()) {
+        Ok(())
+    } else {
+        Err(MtcapError::ChecksumMismatch {
+            expected: expected_sha256.trim().to_ascii_lowercase(),
+            actual,
+        })
+    }
+}
+
+/// Checks the image against its SHA-256, then uploads it to the gateway.
+pub fn upload(token: &Token, image: &Path, expected_sha256: &str) -> Result<(), MtcapError> {
+    verify_checksum(image, expected_sha256)?;
+
+    curl::upload(get_url(token, "command/firmware_pre_upgrade"), image)
+}
+
+/// Starts installing the uploaded image. The gateway reboots once the upgrade is done.
+pub fn upgrade(token: &Token) -> Result<(), MtcapError> {
+    curl::post(get_url(token, "command/firmware_upgrade"))?;
+
+    Ok(())
+}
+
+pub fn status(token: &Token) -> Result<UpgradeStatus, MtcapError> {
+    let gateway_response = curl::get(get_url(token, "system/firmwareUpgradeStatus"))?;
+
+    UpgradeStatus::from_json(&json::parse(&gateway_response)?["result"])
+}
+
+/// Uploads and installs an image, reports progress until the gateway reboots, logs in again and
+/// checks that the gateway now runs `expected_version`.
+pub fn upgrade_and_wait<F: FnMut(&UpgradeStatus)>(
+    gateway: &Gateway,
+    token: &Token,
+    image: &Path,
+    expected_sha256: &str,
+    expected_version: &str,
+    timeout: Duration,
+    mut progress: F,
+) -> Result<Token, MtcapError> {
+    let deadline = Instant::now() + timeout;
+
+    upload(token, image, expected_sha256)?;
+    upgrade(token)?;
+
+    let mut failure = None;
+    let mut done = false;
+    poll_until(deadline, "the gateway to install the firmware", || {
+        match status(token) {
+            Ok(status) => {
+                progress(&status);
+                match status.state {
+                    UpgradeState::Failed => failure = Some(status.message),
+                    UpgradeState::Done => done = true,
+                    _ => return false,
+                }
+                true
+            }
+            // The gateway has gone down to reboot into the new firmware.
+            Err(_) => is_down(gateway),
+        }
+    })?;
+    if let Some(message) = failure {
+        return Err(MtcapError::Other(format!(
+            "Firmware upgrade failed: {message}"
+        )));
+    }
+    if done {
+        // The session does not survive the reboot which follows.
+        poll_until(deadline, "the gateway to reboot", || status(token).is_err())?;
+    }
+
+    thread::sleep(POLL_INTERVAL);
+    let token = wait_for_login(gateway, deadline.saturating_duration_since(Instant::now()))?;
+
+    let firmware_version = info(&token)?.firmware_version().to_string();
+    if firmware_version != expected_version {
+        return Err(MtcapError::Other(format!(
+            "Expected firmware {expected_version} after the upgrade, \
+             but the gateway runs {firmware_version}"
+        )));
+    }
+
+    Ok(token)
+}
+
+#[cfg(test)]
+#[path = "./test_firmware.rs"]
+mod test_firmware;
@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::credentials::{login, logout, Gateway, Token};
+use crate::result::MtcapError;
+
+/// The outcome of an operation on each gateway, in the same order as the gateways given.
+#[derive(Debug)]
+pub struct Report<T> {
+    results: Vec<Result<T, MtcapError>>,
+}
+
+impl<T> Report<T> {
+    pub fn results(&self) -> &[Result<T, MtcapError>] {
+        &self.results
+    }
+
+    pub fn into_results(self) -> Vec<Result<T, MtcapError>> {
+        self.results
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// The index of each gateway on which the operation failed, with its error.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &MtcapError)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|e| (index, e)))
+    }
+}
+
+/// Logs into each gateway, runs `operation` and logs out, on up to `concurrency` gateways at a
+/// time. A failure on one gateway does not stop the others. Logout failures are ignored.
+pub fn run<T, F>(gateways: &[Gateway], concurrency: usize, operation: F) -> Report<T>
+where
+    T: Send,
+    F: Fn(&Token) -> Result<T, MtcapError> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..gateways.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, gateways.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(gateway) = gateways.get(index) else {
+                    break;
+                };
+
+                let result = run_one(gateway, &operation);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    Report {
+        results: results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect(),
+    }
+}
+
+fn run_one<T, F>(gateway: &Gateway, operation: &F) -> Result<T, MtcapError>
+where
+    F: Fn(&Token) -> Result<T, MtcapError>,
+{
+    let token = login(gateway)?;
+    let result = operation(&token);
+    let _ = logout(&token);
+
+    result
+}
+
+#[cfg(test)]
+#[path = "./test_fleet.rs"]
+mod test_fleet;
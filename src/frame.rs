@@ -0,0 +1,947 @@
+use crate::crypto::{self, Direction, SessionKeys, MIC_LENGTH};
+use crate::devices::{Eui, Key};
+use crate::result::MtcapError;
+
+const MHDR_LENGTH: usize = 1;
+const JOIN_REQUEST_LENGTH: usize = 23;
+const JOIN_ACCEPT_LENGTH: usize = 17;
+const JOIN_ACCEPT_CF_LIST_LENGTH: usize = 33;
+const CF_LIST_LENGTH: usize = 16;
+const FHDR_MIN_LENGTH: usize = 7;
+const FOPTS_MAX_LENGTH: usize = 15;
+const LORAWAN_R1: u8 = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessageType {
+    JoinRequest,
+    JoinAccept,
+    UnconfirmedDataUp,
+    UnconfirmedDataDown,
+    ConfirmedDataUp,
+    ConfirmedDataDown,
+    RejoinRequest,
+    Proprietary,
+}
+
+impl MessageType {
+    fn from_mhdr(mhdr: u8) -> Result<Self, MtcapError> {
+        if mhdr & 0x03 != LORAWAN_R1 {
+            return Err(invalid(format!(
+                "unsupported major version {}",
+                mhdr & 0x03
+            )));
+        }
+        Ok(match mhdr >> 5 {
+            0 => Self::JoinRequest,
+            1 => Self::JoinAccept,
+            2 => Self::UnconfirmedDataUp,
+            3 => Self::UnconfirmedDataDown,
+            4 => Self::ConfirmedDataUp,
+            5 => Self::ConfirmedDataDown,
+            6 => Self::RejoinRequest,
+            _ => Self::Proprietary,
+        })
+    }
+
+    fn to_mhdr(self) -> u8 {
+        let mtype = match self {
+            Self::JoinRequest => 0,
+            Self::JoinAccept => 1,
+            Self::UnconfirmedDataUp => 2,
+            Self::UnconfirmedDataDown => 3,
+            Self::ConfirmedDataUp => 4,
+            Self::ConfirmedDataDown => 5,
+            Self::RejoinRequest => 6,
+            Self::Proprietary => 7,
+        };
+        mtype << 5 | LORAWAN_R1
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PhyPayload {
+    JoinRequest(JoinRequest),
+    /// A join-accept as received, including its MHDR. Decrypt it with
+    /// [`crypto::decrypt_join_accept`] and parse the result with [`JoinAccept::parse`].
+    EncryptedJoinAccept(Vec<u8>),
+    Data(DataFrame),
+    /// A proprietary frame, including its MHDR.
+    Proprietary(Vec<u8>),
+}
+
+impl PhyPayload {
+    pub fn parse(bytes: &[u8]) -> Result<Self, MtcapError> {
+        let Some(mhdr) = bytes.first() else {
+            return Err(invalid("PHYPayload is empty"));
+        };
+
+        match MessageType::from_mhdr(*mhdr)? {
+            MessageType::JoinRequest => Ok(Self::JoinRequest(JoinRequest::parse(bytes)?)),
+            MessageType::JoinAccept => {
+                if bytes.len() != JOIN_ACCEPT_LENGTH && bytes.len() != JOIN_ACCEPT_CF_LIST_LENGTH {
+                    return Err(invalid(format!("join-accept has length {}", bytes.len())));
+                }
+                Ok(Self::EncryptedJoinAccept(bytes.to_vec()))
+            }
+            MessageType::UnconfirmedDataUp
+            | MessageType::UnconfirmedDataDown
+            | MessageType::ConfirmedDataUp
+            | MessageType::ConfirmedDataDown => Ok(Self::Data(DataFrame::parse(bytes)?)),
+            MessageType::RejoinRequest => Err(invalid("rejoin-request is not supported")),
+            MessageType::Proprietary => Ok(Self::Proprietary(bytes.to_vec())),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::JoinRequest(join_request) => join_request.to_bytes(),
+            Self::EncryptedJoinAccept(bytes) => bytes.clone(),
+            Self::Data(data_frame) => data_frame.to_bytes(),
+            Self::Proprietary(bytes) => bytes.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JoinRequest {
+    join_eui: Eui,
+    device_eui: Eui,
+    device_nonce: u16,
+    mic: [u8; MIC_LENGTH],
+}
+
+impl JoinRequest {
+    /// Creates an unsigned join-request. Call [`JoinRequest::sign`] before serializing.
+    pub const fn new(join_eui: Eui, device_eui: Eui, device_nonce: u16) -> Self {
+        Self {
+            join_eui,
+            device_eui,
+            device_nonce,
+            mic: [0; MIC_LENGTH],
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, MtcapError> {
+        if bytes.len() != JOIN_REQUEST_LENGTH {
+            return Err(invalid(format!("join-request has length {}", bytes.len())));
+        }
+        if MessageType::from_mhdr(bytes[0])? != MessageType::JoinRequest {
+            return Err(invalid("not a join-request"));
+        }
+
+        let mut reader = Reader::new(&bytes[MHDR_LENGTH..]);
+        Ok(Self {
+            join_eui: reader.eui()?,
+            device_eui: reader.eui()?,
+            device_nonce: reader.u16()?,
+            mic: reader.mic()?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.message();
+        bytes.extend_from_slice(&self.mic);
+        bytes
+    }
+
+    pub fn sign(&mut self, application_key: &Key) {
+        self.mic = crypto::join_mic(application_key, &self.message());
+    }
+
+    pub fn verify_mic(&self, application_key: &Key) -> Result<(), MtcapError> {
+        crypto::verify_join_mic(application_key, &self.to_bytes())
+    }
+
+    pub fn join_eui(&self) -> &Eui {
+        &self.join_eui
+    }
+
+    pub fn device_eui(&self) -> &Eui {
+        &self.device_eui
+    }
+
+    pub fn device_nonce(&self) -> u16 {
+        self.device_nonce
+    }
+
+    pub fn mic(&self) -> [u8; MIC_LENGTH] {
+        self.mic
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let mut bytes = vec![MessageType::JoinRequest.to_mhdr()];
+        push_eui(&mut bytes, &self.join_eui);
+        push_eui(&mut bytes, &self.device_eui);
+        bytes.extend_from_slice(&self.device_nonce.to_le_bytes());
+        bytes
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JoinAccept {
+    join_nonce: u32,
+    net_id: u32,
+    device_address: u32,
+    dl_settings: u8,
+    rx_delay: u8,
+    cf_list: Option<[u8; CF_LIST_LENGTH]>,
+    mic: [u8; MIC_LENGTH],
+}
+
+impl JoinAccept {
+    /// Creates an unsigned join-accept. Call [`JoinAccept::sign`] before serializing.
+    pub const fn new(
+        join_nonce: u32,
+        net_id: u32,
+        device_address: u32,
+        dl_settings: u8,
+        rx_delay: u8,
+        cf_list: Option<[u8; CF_LIST_LENGTH]>,
+    ) -> Self {
+        Self {
+            join_nonce,
+            net_id,
+            device_address,
+            dl_settings,
+            rx_delay,
+            cf_list,
+            mic: [0; MIC_LENGTH],
+        }
+    }
+
+    /// Parses a decrypted join-accept, including its MHDR.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MtcapError> {
+        if bytes.len() != JOIN_ACCEPT_LENGTH && bytes.len() != JOIN_ACCEPT_CF_LIST_LENGTH {
+            return Err(invalid(format!("join-accept has length {}", bytes.len())));
+        }
+        if MessageType::from_mhdr(bytes[0])? != MessageType::JoinAccept {
+            return Err(invalid("not a join-accept"));
+        }
+
+        let mut reader = Reader::new(&bytes[MHDR_LENGTH..]);
+        Ok(Self {
+            join_nonce: reader.u24()?,
+            net_id: reader.u24()?,
+            device_address: reader.u32()?,
+            dl_settings: reader.u8()?,
+            rx_delay: reader.u8()?,
+            cf_list: if bytes.len() == JOIN_ACCEPT_CF_LIST_LENGTH {
+                Some(reader.take(CF_LIST_LENGTH)?.try_into().unwrap())
+            } else {
+                None
+            },
+            mic: reader.mic()?,
+        })
+    }
+
+    /// Serializes the join-accept without encrypting it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.message();
+        bytes.extend_from_slice(&self.mic);
+        bytes
+    }
+
+    pub fn encrypt(&self, application_key: &Key) -> Vec<u8> {
+        crypto::encrypt_join_accept(application_key, &self.to_bytes()).unwrap()
+    }
+
+    pub fn sign(&mut self, application_key: &Key) {
+        self.mic = crypto::join_mic(application_key, &self.message());
+    }
+
+    pub fn verify_mic(&self, application_key: &Key) -> Result<(), MtcapError> {
+        crypto::verify_join_mic(application_key, &self.to_bytes())
+    }
+
+    pub fn session_keys(&self, application_key: &Key, device_nonce: u16) -> SessionKeys {
+        crypto::derive_session_keys(application_key, self.join_nonce, self.net_id, device_nonce)
+    }
+
+    pub fn join_nonce(&self) -> u32 {
+        self.join_nonce
+    }
+
+    pub fn net_id(&self) -> u32 {
+        self.net_id
+    }
+
+    pub fn device_address(&self) -> u32 {
+        self.device_address
+    }
+
+    pub fn dl_settings(&self) -> u8 {
+        self.dl_settings
+    }
+
+    pub fn rx_delay(&self) -> u8 {
+        self.rx_delay
+    }
+
+    pub fn cf_list(&self) -> Option<&[u8; CF_LIST_LENGTH]> {
+        self.cf_list.as_ref()
+    }
+
+    pub fn mic(&self) -> [u8; MIC_LENGTH] {
+        self.mic
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let mut bytes = vec![MessageType::JoinAccept.to_mhdr()];
+        bytes.extend_from_slice(&self.join_nonce.to_le_bytes()[..3]);
+        bytes.extend_from_slice(&self.net_id.to_le_bytes()[..3]);
+        bytes.extend_from_slice(&self.device_address.to_le_bytes());
+        bytes.push(self.dl_settings);
+        bytes.push(self.rx_delay);
+        if let Some(cf_list) = &self.cf_list {
+            bytes.extend_from_slice(cf_list);
+        }
+        bytes
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataFrame {
+    confirmed: bool,
+    direction: Direction,
+    fhdr: Fhdr,
+    port: Option<u8>,
+    frm_payload: Vec<u8>,
+    mic: [u8; MIC_LENGTH],
+}
+
+impl DataFrame {
+    /// Creates an unsigned data frame. `frm_payload` must already be encrypted, and is only sent
+    /// if `port` is set. Call [`DataFrame::sign`] before serializing.
+    pub fn new(
+        confirmed: bool,
+        direction: Direction,
+        fhdr: Fhdr,
+        port: Option<u8>,
+        frm_payload: Vec<u8>,
+    ) -> Self {
+        Self {
+            confirmed,
+            direction,
+            fhdr,
+            port,
+            frm_payload: if port.is_some() {
+                frm_payload
+            } else {
+                Vec::new()
+            },
+            mic: [0; MIC_LENGTH],
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, MtcapError> {
+        if bytes.len() < MHDR_LENGTH + FHDR_MIN_LENGTH + MIC_LENGTH {
+            return Err(invalid(format!("data frame has length {}", bytes.len())));
+        }
+        let (confirmed, direction) = match MessageType::from_mhdr(bytes[0])? {
+            MessageType::UnconfirmedDataUp => (false, Direction::Uplink),
+            MessageType::UnconfirmedDataDown => (false, Direction::Downlink),
+            MessageType::ConfirmedDataUp => (true, Direction::Uplink),
+            MessageType::ConfirmedDataDown => (true, Direction::Downlink),
+            _ => return Err(invalid("not a data frame")),
+        };
+
+        let (message, mic) = bytes.split_at(bytes.len() - MIC_LENGTH);
+        let mut reader = Reader::new(&message[MHDR_LENGTH..]);
+        let fhdr = Fhdr::read(&mut reader, direction)?;
+        let port = if reader.is_empty() {
+            None
+        } else {
+            Some(reader.u8()?)
+        };
+        let frm_payload = reader.rest().to_vec();
+
+        if port == Some(0) && !fhdr.fopts.is_empty() {
+            return Err(invalid("FOpts and port 0 are both present"));
+        }
+
+        Ok(Self {
+            confirmed,
+            direction,
+            fhdr,
+            port,
+            frm_payload,
+            mic: mic.try_into().unwrap(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.message();
+        bytes.extend_from_slice(&self.mic);
+        bytes
+    }
+
+    /// Computes the MIC, where `frame_count` is the full 32-bit frame counter.
+    pub fn sign(&mut self, network_session_key: &Key, frame_count: u32) {
+        self.mic = crypto::data_mic(
+            network_session_key,
+            self.direction,
+            self.fhdr.device_address,
+            frame_count,
+            &self.message(),
+        );
+    }
+
+    /// Verifies the MIC, where `frame_count` is the full 32-bit frame counter.
+    pub fn verify_mic(
+        &self,
+        network_session_key: &Key,
+        frame_count: u32,
+    ) -> Result<(), MtcapError> {
+        crypto::verify_data_mic(
+            network_session_key,
+            self.direction,
+            self.fhdr.device_address,
+            frame_count,
+            &self.to_bytes(),
+        )
+    }
+
+    /// Decrypts the FRMPayload with the session key selected by the port, where `frame_count` is
+    /// the full 32-bit frame counter. On port 0 the result can be parsed with
+    /// [`MacCommand::parse_all`].
+    pub fn decrypt_frm_payload(&self, session_keys: &SessionKeys, frame_count: u32) -> Vec<u8> {
+        let key = if self.port == Some(0) {
+            session_keys.network_session_key()
+        } else {
+            session_keys.application_session_key()
+        };
+        crypto::decrypt_frm_payload(
+            key,
+            self.direction,
+            self.fhdr.device_address,
+            frame_count,
+            &self.frm_payload,
+        )
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        match (self.confirmed, self.direction) {
+            (false, Direction::Uplink) => MessageType::UnconfirmedDataUp,
+            (false, Direction::Downlink) => MessageType::UnconfirmedDataDown,
+            (true, Direction::Uplink) => MessageType::ConfirmedDataUp,
+            (true, Direction::Downlink) => MessageType::ConfirmedDataDown,
+        }
+    }
+
+    pub fn confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn fhdr(&self) -> &Fhdr {
+        &self.fhdr
+    }
+
+    pub fn port(&self) -> Option<u8> {
+        self.port
+    }
+
+    /// The FRMPayload as transmitted, that is encrypted.
+    pub fn frm_payload(&self) -> &[u8] {
+        &self.frm_payload
+    }
+
+    pub fn mic(&self) -> [u8; MIC_LENGTH] {
+        self.mic
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let mut bytes = vec![self.message_type().to_mhdr()];
+        self.fhdr.write(&mut bytes);
+        if let Some(port) = self.port {
+            bytes.push(port);
+            bytes.extend_from_slice(&self.frm_payload);
+        }
+        bytes
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fhdr {
+    device_address: u32,
+    fctrl: FCtrl,
+    frame_count: u16,
+    fopts: Vec<MacCommand>,
+}
+
+impl Fhdr {
+    pub fn new(
+        device_address: u32,
+        fctrl: FCtrl,
+        frame_count: u16,
+        fopts: Vec<MacCommand>,
+    ) -> Result<Self, MtcapError> {
+        let fopts_length: usize = fopts.iter().map(|command| command.to_bytes().len()).sum();
+        if fopts_length > FOPTS_MAX_LENGTH {
+            return Err(invalid(format!(
+                "FOpts of length {fopts_length} exceeds {FOPTS_MAX_LENGTH}"
+            )));
+        }
+
+        Ok(Self {
+            device_address,
+            fctrl,
+            frame_count,
+            fopts,
+        })
+    }
+
+    pub fn device_address(&self) -> u32 {
+        self.device_address
+    }
+
+    pub fn fctrl(&self) -> FCtrl {
+        self.fctrl
+    }
+
+    /// The 16 least significant bits of the frame counter.
+    pub fn frame_count(&self) -> u16 {
+        self.frame_count
+    }
+
+    pub fn fopts(&self) -> &[MacCommand] {
+        &self.fopts
+    }
+
+    fn read(reader: &mut Reader, direction: Direction) -> Result<Self, MtcapError> {
+        let device_address = reader.u32()?;
+        let fctrl_byte = reader.u8()?;
+        let frame_count = reader.u16()?;
+        let fopts = MacCommand::parse_all(direction, reader.take((fctrl_byte & 0x0f) as usize)?)?;
+
+        Ok(Self {
+            device_address,
+            fctrl: FCtrl::from_u8(fctrl_byte),
+            frame_count,
+            fopts,
+        })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let fopts: Vec<u8> = self.fopts.iter().flat_map(MacCommand::to_bytes).collect();
+
+        bytes.extend_from_slice(&self.device_address.to_le_bytes());
+        bytes.push(self.fctrl.to_u8() | fopts.len() as u8);
+        bytes.extend_from_slice(&self.frame_count.to_le_bytes());
+        bytes.extend_from_slice(&fopts);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FCtrl {
+    pub adr: bool,
+    pub adr_ack_req: bool,
+    pub ack: bool,
+    /// FPending on downlinks, ClassB on uplinks.
+    pub pending: bool,
+}
+
+impl FCtrl {
+    fn from_u8(byte: u8) -> Self {
+        Self {
+            adr: byte & 0x80 != 0,
+            adr_ack_req: byte & 0x40 != 0,
+            ack: byte & 0x20 != 0,
+            pending: byte & 0x10 != 0,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        (self.adr as u8) << 7
+            | (self.adr_ack_req as u8) << 6
+            | (self.ack as u8) << 5
+            | (self.pending as u8) << 4
+    }
+}
+
+/// LoRaWAN 1.0.x MAC commands. Frequencies are in Hz.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MacCommand {
+    LinkCheckReq,
+    LinkCheckAns {
+        margin: u8,
+        gateway_count: u8,
+    },
+    LinkAdrReq {
+        data_rate: u8,
+        tx_power: u8,
+        channel_mask: u16,
+        redundancy: u8,
+    },
+    LinkAdrAns {
+        status: u8,
+    },
+    DutyCycleReq {
+        max_duty_cycle: u8,
+    },
+    DutyCycleAns,
+    RxParamSetupReq {
+        dl_settings: u8,
+        frequency: u32,
+    },
+    RxParamSetupAns {
+        status: u8,
+    },
+    DevStatusReq,
+    DevStatusAns {
+        battery: u8,
+        margin: i8,
+    },
+    NewChannelReq {
+        channel_index: u8,
+        frequency: u32,
+        dr_range: u8,
+    },
+    NewChannelAns {
+        status: u8,
+    },
+    RxTimingSetupReq {
+        settings: u8,
+    },
+    RxTimingSetupAns,
+    TxParamSetupReq {
+        eirp_dwell_time: u8,
+    },
+    TxParamSetupAns,
+    DlChannelReq {
+        channel_index: u8,
+        frequency: u32,
+    },
+    DlChannelAns {
+        status: u8,
+    },
+    DeviceTimeReq,
+    DeviceTimeAns {
+        seconds: u32,
+        fractional: u8,
+    },
+    PingSlotInfoReq {
+        periodicity: u8,
+    },
+    PingSlotInfoAns,
+    PingSlotChannelReq {
+        frequency: u32,
+        data_rate: u8,
+    },
+    PingSlotChannelAns {
+        status: u8,
+    },
+    BeaconTimingReq,
+    BeaconTimingAns {
+        delay: u16,
+        channel: u8,
+    },
+    BeaconFreqReq {
+        frequency: u32,
+    },
+    BeaconFreqAns {
+        status: u8,
+    },
+    /// A command this crate does not model. Its length is unknown, so it runs to the end of the
+    /// payload.
+    Unknown {
+        cid: u8,
+        payload: Vec<u8>,
+    },
+    /// A proprietary command, which runs to the end of the payload.
+    Proprietary {
+        cid: u8,
+        payload: Vec<u8>,
+    },
+}
+
+impl MacCommand {
+    /// Parses a sequence of MAC commands, as found in FOpts or a decrypted port 0 FRMPayload.
+    pub fn parse_all(direction: Direction, bytes: &[u8]) -> Result<Vec<Self>, MtcapError> {
+        let mut reader = Reader::new(bytes);
+        let mut commands = Vec::new();
+        while !reader.is_empty() {
+            commands.push(Self::read(&mut reader, direction)?);
+        }
+        Ok(commands)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::LinkCheckReq => vec![0x02],
+            Self::LinkCheckAns {
+                margin,
+                gateway_count,
+            } => vec![0x02, *margin, *gateway_count],
+            Self::LinkAdrReq {
+                data_rate,
+                tx_power,
+                channel_mask,
+                redundancy,
+            } => {
+                let mut bytes = vec![0x03, data_rate << 4 | (tx_power & 0x0f)];
+                bytes.extend_from_slice(&channel_mask.to_le_bytes());
+                bytes.push(*redundancy);
+                bytes
+            }
+            Self::LinkAdrAns { status } => vec![0x03, *status],
+            Self::DutyCycleReq { max_duty_cycle } => vec![0x04, *max_duty_cycle],
+            Self::DutyCycleAns => vec![0x04],
+            Self::RxParamSetupReq {
+                dl_settings,
+                frequency,
+            } => {
+                let mut bytes = vec![0x05, *dl_settings];
+                push_frequency(&mut bytes, *frequency);
+                bytes
+            }
+            Self::RxParamSetupAns { status } => vec![0x05, *status],
+            Self::DevStatusReq => vec![0x06],
+            Self::DevStatusAns { battery, margin } => vec![0x06, *battery, *margin as u8 & 0x3f],
+            Self::NewChannelReq {
+                channel_index,
+                frequency,
+                dr_range,
+            } => {
+                let mut bytes = vec![0x07, *channel_index];
+                push_frequency(&mut bytes, *frequency);
+                bytes.push(*dr_range);
+                bytes
+            }
+            Self::NewChannelAns { status } => vec![0x07, *status],
+            Self::RxTimingSetupReq { settings } => vec![0x08, *settings],
+            Self::RxTimingSetupAns => vec![0x08],
+            Self::TxParamSetupReq { eirp_dwell_time } => vec![0x09, *eirp_dwell_time],
+            Self::TxParamSetupAns => vec![0x09],
+            Self::DlChannelReq {
+                channel_index,
+                frequency,
+            } => {
+                let mut bytes = vec![0x0a, *channel_index];
+                push_frequency(&mut bytes, *frequency);
+                bytes
+            }
+            Self::DlChannelAns { status } => vec![0x0a, *status],
+            Self::DeviceTimeReq => vec![0x0d],
+            Self::DeviceTimeAns {
+                seconds,
+                fractional,
+            } => {
+                let mut bytes = vec![0x0d];
+                bytes.extend_from_slice(&seconds.to_le_bytes());
+                bytes.push(*fractional);
+                bytes
+            }
+            Self::PingSlotInfoReq { periodicity } => vec![0x10, *periodicity & 0x07],
+            Self::PingSlotInfoAns => vec![0x10],
+            Self::PingSlotChannelReq {
+                frequency,
+                data_rate,
+            } => {
+                let mut bytes = vec![0x11];
+                push_frequency(&mut bytes, *frequency);
+                bytes.push(*data_rate);
+                bytes
+            }
+            Self::PingSlotChannelAns { status } => vec![0x11, *status],
+            Self::BeaconTimingReq => vec![0x12],
+            Self::BeaconTimingAns { delay, channel } => {
+                let mut bytes = vec![0x12];
+                bytes.extend_from_slice(&delay.to_le_bytes());
+                bytes.push(*channel);
+                bytes
+            }
+            Self::BeaconFreqReq { frequency } => {
+                let mut bytes = vec![0x13];
+                push_frequency(&mut bytes, *frequency);
+                bytes
+            }
+            Self::BeaconFreqAns { status } => vec![0x13, *status],
+            Self::Unknown { cid, payload } | Self::Proprietary { cid, payload } => {
+                let mut bytes = vec![*cid];
+                bytes.extend_from_slice(payload);
+                bytes
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader, direction: Direction) -> Result<Self, MtcapError> {
+        let cid = reader.u8()?;
+        let command = match (direction, cid) {
+            (_, 0x80..=0xff) => Self::Proprietary {
+                cid,
+                payload: reader.rest().to_vec(),
+            },
+            (Direction::Uplink, 0x02) => Self::LinkCheckReq,
+            (Direction::Uplink, 0x03) => Self::LinkAdrAns {
+                status: reader.u8()?,
+            },
+            (Direction::Uplink, 0x04) => Self::DutyCycleAns,
+            (Direction::Uplink, 0x05) => Self::RxParamSetupAns {
+                status: reader.u8()?,
+            },
+            (Direction::Uplink, 0x06) => {
+                let battery = reader.u8()?;
+                // The margin is a 6-bit signed integer.
+                let margin = ((reader.u8()? << 2) as i8) >> 2;
+                Self::DevStatusAns { battery, margin }
+            }
+            (Direction::Uplink, 0x07) => Self::NewChannelAns {
+                status: reader.u8()?,
+            },
+            (Direction::Uplink, 0x08) => Self::RxTimingSetupAns,
+            (Direction::Uplink, 0x09) => Self::TxParamSetupAns,
+            (Direction::Uplink, 0x0a) => Self::DlChannelAns {
+                status: reader.u8()?,
+            },
+            (Direction::Uplink, 0x0d) => Self::DeviceTimeReq,
+            (Direction::Uplink, 0x10) => Self::PingSlotInfoReq {
+                periodicity: reader.u8()? & 0x07,
+            },
+            (Direction::Uplink, 0x11) => Self::PingSlotChannelAns {
+                status: reader.u8()?,
+            },
+            (Direction::Uplink, 0x12) => Self::BeaconTimingReq,
+            (Direction::Uplink, 0x13) => Self::BeaconFreqAns {
+                status: reader.u8()?,
+            },
+            (Direction::Downlink, 0x02) => Self::LinkCheckAns {
+                margin: reader.u8()?,
+                gateway_count: reader.u8()?,
+            },
+            (Direction::Downlink, 0x03) => {
+                let data_rate_tx_power = reader.u8()?;
+                Self::LinkAdrReq {
+                    data_rate: data_rate_tx_power >> 4,
+                    tx_power: data_rate_tx_power & 0x0f,
+                    channel_mask: reader.u16()?,
+                    redundancy: reader.u8()?,
+                }
+            }
+            (Direction::Downlink, 0x04) => Self::DutyCycleReq {
+                max_duty_cycle: reader.u8()?,
+            },
+            (Direction::Downlink, 0x05) => Self::RxParamSetupReq {
+                dl_settings: reader.u8()?,
+                frequency: reader.frequency()?,
+            },
+            (Direction::Downlink, 0x06) => Self::DevStatusReq,
+            (Direction::Downlink, 0x07) => Self::NewChannelReq {
+                channel_index: reader.u8()?,
+                frequency: reader.frequency()?,
+                dr_range: reader.u8()?,
+            },
+            (Direction::Downlink, 0x08) => Self::RxTimingSetupReq {
+                settings: reader.u8()?,
+            },
+            (Direction::Downlink, 0x09) => Self::TxParamSetupReq {
+                eirp_dwell_time: reader.u8()?,
+            },
+            (Direction::Downlink, 0x0a) => Self::DlChannelReq {
+                channel_index: reader.u8()?,
+                frequency: reader.frequency()?,
+            },
+            (Direction::Downlink, 0x0d) => Self::DeviceTimeAns {
+                seconds: reader.u32()?,
+                fractional: reader.u8()?,
+            },
+            (Direction::Downlink, 0x10) => Self::PingSlotInfoAns,
+            (Direction::Downlink, 0x11) => Self::PingSlotChannelReq {
+                frequency: reader.frequency()?,
+                data_rate: reader.u8()?,
+            },
+            (Direction::Downlink, 0x12) => Self::BeaconTimingAns {
+                delay: reader.u16()?,
+                channel: reader.u8()?,
+            },
+            (Direction::Downlink, 0x13) => Self::BeaconFreqReq {
+                frequency: reader.frequency()?,
+            },
+            _ => Self::Unknown {
+                cid,
+                payload: reader.rest().to_vec(),
+            },
+        };
+
+        Ok(command)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], MtcapError> {
+        if self.bytes.len() < length {
+            return Err(invalid("frame is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = self.bytes;
+        self.bytes = &[];
+        rest
+    }
+
+    fn u8(&mut self) -> Result<u8, MtcapError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MtcapError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u24(&mut self) -> Result<u32, MtcapError> {
+        let bytes = self.take(3)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MtcapError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn frequency(&mut self) -> Result<u32, MtcapError> {
+        Ok(self.u24()? * 100)
+    }
+
+    fn eui(&mut self) -> Result<Eui, MtcapError> {
+        let mut digits: [u8; 8] = self.take(8)?.try_into().unwrap();
+        digits.reverse();
+        Ok(Eui::new(digits))
+    }
+
+    fn mic(&mut self) -> Result<[u8; MIC_LENGTH], MtcapError> {
+        Ok(self.take(MIC_LENGTH)?.try_into().unwrap())
+    }
+}
+
+fn push_eui(bytes: &mut Vec<u8>, eui: &Eui) {
+    bytes.extend(eui.digits().iter().rev());
+}
+
+fn push_frequency(bytes: &mut Vec<u8>, frequency: u32) {
+    bytes.extend_from_slice(&(frequency / 100).to_le_bytes()[..3]);
+}
+
+fn invalid<T: Into<String>>(message: T) -> MtcapError {
+    MtcapError::InvalidFrame(message.into())
+}
+
+#[cfg(test)]
+#[path = "./test_frame.rs"]
+mod test_frame;
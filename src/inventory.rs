@@ -0,0 +1,155 @@
+use std::fs;
+use std::os::windows::process::CommandExt as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::credentials::{Address, Gateway};
+use crate::curl::CREATE_NO_WINDOW;
+use crate::result::MtcapError;
+
+/// A list of gateways, loaded from a TOML or JSON file such as:
+///
+/// ```toml
+/// [[gateways]]
+/// name = "site-a"
+/// address = "192.168.2.1"
+/// username = "admin"
+/// password = { env = "SITE_A_PASSWORD" }
+/// ```
+///
+/// The address may be a host name, IPv4 or IPv6 address, with optional scheme, port and base path,
+/// as parsed by [`Address`]. The password may instead come from `{ file = "path" }`, whose first
+/// line is used, or `{ command = "..." }`, whose output is used.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Inventory {
+    gateways: Vec<GatewayEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct GatewayEntry {
+    name: String,
+    address: String,
+    username: String,
+    password: PasswordSource,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordSource {
+    Env(String),
+    File(PathBuf),
+    Command(String),
+}
+
+impl Inventory {
+    /// Loads an inventory, as JSON if the file extension is `json` and as TOML otherwise.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MtcapError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            Self::from_json(&contents)
+        } else {
+            Self::from_toml(&contents)
+        }
+        .map_err(|e| MtcapError::Other(format!("{}: {e}", path.display())))
+    }
+
+    pub fn from_toml(input: &str) -> Result<Self, MtcapError> {
+        toml::from_str(input).map_err(|e| MtcapError::Other(e.to_string()))
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, MtcapError> {
+        serde_json::from_str(input).map_err(|e| MtcapError::Other(e.to_string()))
+    }
+
+    pub fn entries(&self) -> &[GatewayEntry] {
+        &self.gateways
+    }
+
+    pub fn entry(&self, name: &str) -> Result<&GatewayEntry, MtcapError> {
+        self.gateways
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| MtcapError::Other(format!("{name} is not in the inventory")))
+    }
+
+    /// Resolves the password of every gateway.
+    pub fn gateways(&self) -> Result<Vec<Gateway>, MtcapError> {
+        self.gateways.iter().map(GatewayEntry::gateway).collect()
+    }
+}
+
+impl GatewayEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn password_source(&self) -> &PasswordSource {
+        &self.password
+    }
+
+    /// Resolves the password and creates the gateway.
+    pub fn gateway(&self) -> Result<Gateway, MtcapError> {
+        let address: Address = self
+            .address
+            .parse()
+            .map_err(|e| MtcapError::Other(format!("{}: {e}", self.name)))?;
+        let password = self
+            .password
+            .resolve()
+            .map_err(|e| MtcapError::Other(format!("{}: password: {e}", self.name)))?;
+
+        Ok(Gateway::with_address(
+            address,
+            self.username.clone(),
+            password,
+        ))
+    }
+}
+
+impl PasswordSource {
+    pub fn resolve(&self) -> Result<String, MtcapError> {
+        match self {
+            Self::Env(variable) => std::env::var(variable)
+                .map_err(|e| MtcapError::Other(format!("environment variable {variable}: {e}"))),
+            Self::File(path) => {
+                let contents = fs::read_to_string(path)?;
+                Ok(contents.lines().next().unwrap_or_default().to_string())
+            }
+            Self::Command(command) => {
+                let output = Command::new("cmd")
+                    .arg("/C")
+                    .arg(command)
+                    .creation_flags(CREATE_NO_WINDOW)
+                    .output()?;
+                if !output.status.success() {
+                    return Err(MtcapError::Other(format!(
+                        "{command} failed with {}",
+                        output.status
+                    )));
+                }
+                Ok(String::from_utf8_lossy(&output.stdout)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./test_inventory.rs"]
+mod test_inventory;
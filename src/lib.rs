@@ -1,9 +1,27 @@
 mod credentials;
-pub use credentials::{login, logout, Gateway, Token};
+pub use credentials::{
+    apply, login, logout, save, save_apply, wait_for_apply, Address, Gateway, Token,
+};
+pub mod backup;
+pub mod cellular;
+pub mod clock;
+pub mod commissioning;
+pub mod crypto;
 mod curl;
 pub mod devices;
-pub mod devices_fix;
+pub mod firmware;
+pub mod fleet;
+pub mod frame;
 pub use devices::{Class, Device, DeviceProfile, Eui, Key};
+pub mod inventory;
+pub mod migration;
 pub mod network;
 pub mod queue;
+pub mod region;
 mod result;
+pub use result::MtcapError;
+pub mod system;
+#[cfg(test)]
+#[path = "./test_server.rs"]
+mod test_server;
+pub mod users;
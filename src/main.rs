@@ -0,0 +1,492 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use mtcap::devices::{self, ClearStrategy, DeviceRecord, DeviceSnapshot, Filter, Selection};
+use mtcap::inventory::{Inventory, PasswordSource};
+use mtcap::network::{self, Mode};
+use mtcap::{fleet, queue, Class, Device, DeviceProfile, Eui, Gateway, Key, MtcapError, Token};
+
+/// Communication with MultiTech Conduit AP gateways.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    gateways: GatewayArgs,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = Format::Table, global = true)]
+    output: Format,
+
+    /// Maximum number of gateways to talk to at once.
+    #[arg(long, default_value_t = 8, global = true)]
+    concurrency: usize,
+
+    /// Directory in which to save the allowlist and devices before removing devices.
+    #[arg(long, global = true)]
+    snapshot_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct GatewayArgs {
+    /// Gateway address: host name, IPv4 or IPv6 address, with optional scheme, port and path.
+    #[arg(long, conflicts_with = "inventory", requires = "username")]
+    address: Option<String>,
+
+    #[arg(long)]
+    username: Option<String>,
+
+    /// Environment variable holding the password.
+    #[arg(long, group = "password")]
+    password_env: Option<String>,
+
+    /// File whose first line is the password.
+    #[arg(long, group = "password")]
+    password_file: Option<PathBuf>,
+
+    /// Command printing the password.
+    #[arg(long, group = "password")]
+    password_command: Option<String>,
+
+    /// Gateway inventory file (TOML or JSON).
+    #[arg(long, global = true)]
+    inventory: Option<PathBuf>,
+
+    /// Gateway name in the inventory. Repeat for several gateways. Defaults to every gateway,
+    /// except for commands which remove or replace devices, which need --gateway or --all.
+    #[arg(long = "gateway", requires = "inventory", global = true)]
+    names: Vec<String>,
+
+    /// Every gateway in the inventory.
+    #[arg(long, requires = "inventory", conflicts_with = "names", global = true)]
+    all: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Checks that login succeeds.
+    Login,
+    /// Manages the allowlist and devices.
+    #[command(subcommand)]
+    Devices(DevicesCommand),
+    /// Manages queued downlinks.
+    #[command(subcommand)]
+    Queue(QueueCommand),
+    /// Manages the LoRa network settings.
+    #[command(subcommand)]
+    Network(NetworkCommand),
+}
+
+#[derive(Subcommand)]
+enum DevicesCommand {
+    /// Lists devices.
+    List {
+        /// Only devices never joined.
+        #[arg(long)]
+        never_joined: bool,
+        /// Only devices last seen before this date.
+        #[arg(long)]
+        last_seen_before: Option<NaiveDate>,
+    },
+    /// Counts the devices in the gateway's device list, as listed by `list`, rather than the
+    /// allowlist entries.
+    Count,
+    /// Adds or updates a device in the allowlist.
+    Add {
+        #[arg(long, value_parser = Eui::from_str)]
+        device_eui: Eui,
+        #[arg(long, value_parser = Eui::from_str)]
+        join_eui: Eui,
+        #[arg(long, value_parser = Key::from_str)]
+        application_key: Key,
+        #[arg(long, value_parser = Class::from_str, default_value = "A")]
+        class: Class,
+        #[arg(long, value_parser = DeviceProfile::from_str)]
+        device_profile: DeviceProfile,
+        #[arg(long, value_parser = Class::from_str, default_value = "A")]
+        network_profile: Class,
+    },
+    /// Removes devices.
+    Remove {
+        #[arg(required = true, value_parser = Eui::from_str)]
+        device_euis: Vec<Eui>,
+    },
+    /// Removes devices last seen, or if never seen created, before a date.
+    RemoveOld { older_than: NaiveDate },
+    /// Removes every device.
+    Clear {
+        /// Deletes each device without emptying the allowlist.
+        #[arg(long)]
+        one_by_one: bool,
+    },
+    /// Restores the allowlist and devices saved by --snapshot-dir.
+    Rollback { snapshot: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum QueueCommand {
+    /// Lists queued downlinks.
+    List,
+    /// Removes the queued downlinks of devices.
+    Remove {
+        #[arg(required = true, value_parser = Eui::from_str)]
+        device_euis: Vec<Eui>,
+    },
+}
+
+#[derive(Subcommand)]
+enum NetworkCommand {
+    /// Gets or sets the LoRa mode.
+    #[command(subcommand)]
+    Mode(ModeCommand),
+}
+
+#[derive(Subcommand)]
+enum ModeCommand {
+    /// Gets the LoRa mode.
+    Get,
+    /// Sets the LoRa mode.
+    Set {
+        #[arg(value_enum)]
+        mode: ModeArg,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ModeArg {
+    NetworkServer,
+    PacketForwarder,
+    Disabled,
+}
+
+impl Command {
+    /// Whether the command removes or replaces devices, so must not run on a whole inventory by
+    /// default.
+    fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            Self::Devices(
+                DevicesCommand::Remove { .. }
+                    | DevicesCommand::RemoveOld { .. }
+                    | DevicesCommand::Clear { .. }
+                    | DevicesCommand::Rollback { .. }
+            ) | Self::Queue(QueueCommand::Remove { .. })
+        )
+    }
+}
+
+impl From<Mode> for ModeArg {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::NetworkServer => ModeArg::NetworkServer,
+            Mode::PacketForwarder => ModeArg::PacketForwarder,
+            Mode::Disabled => ModeArg::Disabled,
+        }
+    }
+}
+
+impl From<ModeArg> for Mode {
+    fn from(mode: ModeArg) -> Self {
+        match mode {
+            ModeArg::NetworkServer => Mode::NetworkServer,
+            ModeArg::PacketForwarder => Mode::PacketForwarder,
+            ModeArg::Disabled => Mode::Disabled,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let (names, gateways) = match gateways(&cli.gateways, cli.command.is_destructive()) {
+        Ok(gateways) => gateways,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = fleet::run(&gateways, cli.concurrency, |token| {
+        let token = token.clone().with_snapshot_dir(cli.snapshot_dir.clone());
+        run(&token, &cli.command)
+    });
+    let results: Vec<_> = names.into_iter().zip(report.into_results()).collect();
+
+    match cli.output {
+        Format::Table => print_table(&results),
+        Format::Json => print_json(&results),
+    }
+
+    if results.iter().all(|(_, result)| result.is_ok()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn gateways(
+    args: &GatewayArgs,
+    destructive: bool,
+) -> Result<(Vec<String>, Vec<Gateway>), MtcapError> {
+    if let Some(path) = &args.inventory {
+        if destructive && args.names.is_empty() && !args.all {
+            return Err(MtcapError::Other(
+                "Give --gateway, or --all to run this on every gateway in the inventory"
+                    .to_string(),
+            ));
+        }
+
+        let inventory = Inventory::load(path)?;
+        let entries = if args.names.is_empty() {
+            inventory.entries().iter().collect()
+        } else {
+            args.names
+                .iter()
+                .map(|name| inventory.entry(name))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let names = entries
+            .iter()
+            .map(|entry| entry.name().to_string())
+            .collect();
+        let gateways = entries
+            .iter()
+            .map(|entry| entry.gateway())
+            .collect::<Result<_, _>>()?;
+
+        return Ok((names, gateways));
+    }
+
+    let (Some(address), Some(username)) = (&args.address, &args.username) else {
+        return Err(MtcapError::Other(
+            "Give either --inventory or --address and --username".to_string(),
+        ));
+    };
+    let password_source = if let Some(variable) = &args.password_env {
+        PasswordSource::Env(variable.clone())
+    } else if let Some(path) = &args.password_file {
+        PasswordSource::File(path.clone())
+    } else if let Some(command) = &args.password_command {
+        PasswordSource::Command(command.clone())
+    } else {
+        return Err(MtcapError::Other(
+            "Give one of --password-env, --password-file or --password-command".to_string(),
+        ));
+    };
+    let gateway = Gateway::with_address(
+        address.parse()?,
+        username.clone(),
+        password_source.resolve()?,
+    );
+
+    Ok((vec![address.clone()], vec![gateway]))
+}
+
+fn run(token: &Token, command: &Command) -> Result<json::JsonValue, MtcapError> {
+    let output = match command {
+        Command::Login => "ok".into(),
+        Command::Devices(DevicesCommand::List {
+            never_joined,
+            last_seen_before,
+        }) => {
+            let mut filter = Filter::new();
+            if *never_joined {
+                filter = filter.never_joined();
+            }
+            if let Some(date) = last_seen_before {
+                filter = filter.last_seen_before(date.and_time(Default::default()).and_utc());
+            }
+            selection_json(&devices::list(token, &filter)?)
+        }
+        Command::Devices(DevicesCommand::Count) => {
+            let (count, unparseable) = devices::count(token, &Filter::new())?;
+            json::object! { count: count, unparseable: unparseable.len() }
+        }
+        Command::Devices(DevicesCommand::Add {
+            device_eui,
+            join_eui,
+            application_key,
+            class,
+            device_profile,
+            network_profile,
+        }) => {
+            let device = Device::new(
+                device_eui.clone(),
+                join_eui.clone(),
+                application_key.clone(),
+                *class,
+                *device_profile,
+                *network_profile,
+            );
+            devices::add(token, &[device])?;
+            "ok".into()
+        }
+        Command::Devices(DevicesCommand::Remove { device_euis }) => {
+            devices::remove(token, device_euis)?;
+            "ok".into()
+        }
+        Command::Devices(DevicesCommand::RemoveOld { older_than }) => {
+            selection_json(&devices::remove_old(token, *older_than)?)
+        }
+        Command::Devices(DevicesCommand::Clear { one_by_one }) => {
+            let strategy = if *one_by_one {
+                ClearStrategy::OneByOne
+            } else {
+                ClearStrategy::Allowlist
+            };
+            devices::clear_with(token, strategy, |progress| {
+                if let Some(e) = progress.error {
+                    eprintln!("{}: {e}", progress.device_eui);
+                }
+            })?;
+            "ok".into()
+        }
+        Command::Devices(DevicesCommand::Rollback { snapshot }) => {
+            let rollback = devices::rollback(token, &DeviceSnapshot::load(snapshot)?)?;
+            for (device_eui, e) in rollback.sessions_failed() {
+                eprintln!("{device_eui}: {e}");
+            }
+            json::object! {
+                created: rollback.sessions_created().len(),
+                failed: rollback.sessions_failed().len(),
+            }
+        }
+        Command::Queue(QueueCommand::List) => queue::get(token)?
+            .iter()
+            .map(|packet| {
+                json::object! {
+                    deveui: packet.device_eui().to_string(),
+                    port: packet.port(),
+                    data: packet.data(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .into(),
+        Command::Queue(QueueCommand::Remove { device_euis }) => {
+            queue::remove(token, device_euis)?;
+            "ok".into()
+        }
+        Command::Network(NetworkCommand::Mode(ModeCommand::Get)) => {
+            // As `set` takes it, for example `network-server`.
+            let mode = ModeArg::from(network::get_mode(token)?);
+            mode.to_possible_value().unwrap().get_name().into()
+        }
+        Command::Network(NetworkCommand::Mode(ModeCommand::Set { mode })) => {
+            network::set_mode(token, (*mode).into())?;
+            "ok".into()
+        }
+    };
+
+    Ok(output)
+}
+
+fn selection_json(selection: &Selection) -> json::JsonValue {
+    let optional = |value: Option<String>| value.map_or(json::JsonValue::Null, Into::into);
+    let device_json = |device: &DeviceRecord| {
+        json::object! {
+            deveui: device.device_eui().to_string(),
+            class: optional(device.class().map(|class| class.to_string())),
+            device_profile: optional(device.device_profile().map(|profile| profile.to_string())),
+            created_at: optional(device.created_at().map(|time| time.to_rfc3339())),
+            last_seen: optional(device.last_seen().map(|time| time.to_rfc3339())),
+        }
+    };
+
+    selection
+        .devices()
+        .iter()
+        .map(device_json)
+        .chain(selection.unparseable().iter().map(|device| {
+            json::object! {
+                error: device.error(),
+                json: device.json(),
+            }
+        }))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn print_json(results: &[(String, Result<json::JsonValue, MtcapError>)]) {
+    let output: Vec<_> = results
+        .iter()
+        .map(|(name, result)| match result {
+            Ok(value) => json::object! { gateway: name.as_str(), result: value.clone() },
+            Err(e) => json::object! { gateway: name.as_str(), error: e.to_string() },
+        })
+        .collect();
+
+    println!("{}", json::JsonValue::from(output).pretty(2));
+}
+
+fn print_table(results: &[(String, Result<json::JsonValue, MtcapError>)]) {
+    let mut columns = vec!["gateway".to_string()];
+    let mut rows = Vec::new();
+
+    for (name, result) in results {
+        let values = match result {
+            Ok(json::JsonValue::Array(values)) => values.clone(),
+            Ok(value) if value.is_object() => vec![value.clone()],
+            Ok(value) => vec![json::object! { result: value.clone() }],
+            Err(e) => vec![json::object! { error: e.to_string() }],
+        };
+
+        for value in values {
+            let mut row = vec![(String::from("gateway"), name.clone())];
+            for (key, field) in value.entries() {
+                if !columns.iter().any(|column| column == key) {
+                    columns.push(key.to_string());
+                }
+                let field = if field.is_null() {
+                    String::new()
+                } else {
+                    field.to_string()
+                };
+                row.push((key.to_string(), field));
+            }
+            rows.push(row);
+        }
+    }
+
+    let cell = |row: &[(String, String)], column: &str| {
+        row.iter()
+            .find(|(key, _)| key == column)
+            .map_or(String::new(), |(_, value)| value.clone())
+    };
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            rows.iter()
+                .map(|row| cell(row, column).len())
+                .chain([column.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let print_row = |cells: Vec<String>| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(columns.clone());
+    for row in &rows {
+        print_row(columns.iter().map(|column| cell(row, column)).collect());
+    }
+}
@@ -0,0 +1,196 @@
+use std::str::FromStr;
+
+use crate::credentials::{get_url, save_apply_and_wait, Token};
+use crate::curl;
+use crate::devices::{self, create_session, euis, get_devices_json, Eui};
+use crate::result::MtcapError;
+
+/// The allowlist fields which must read back unchanged from the destination.
+const VERIFIED_FIELDS: [&str; 5] = [
+    "appeui",
+    "appkey",
+    "class",
+    "device_profile_id",
+    "network_profile_id",
+];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MigrationOptions {
+    /// Also copies the active sessions, including frame counters, so devices need not rejoin.
+    pub copy_sessions: bool,
+    /// Removes the devices from the source gateway once they are verified on the destination.
+    pub remove_from_source: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Migration {
+    devices: Vec<Eui>,
+    sessions_copied: Vec<Eui>,
+    sessions_failed: Vec<(Eui, MtcapError)>,
+}
+
+impl Migration {
+    /// The devices copied to the destination allowlist.
+    pub fn devices(&self) -> &[Eui] {
+        &self.devices
+    }
+
+    pub fn sessions_copied(&self) -> &[Eui] {
+        &self.sessions_copied
+    }
+
+    /// The sessions which the destination gateway refused. These devices will need to rejoin.
+    pub fn sessions_failed(&self) -> &[(Eui, MtcapError)] {
+        &self.sessions_failed
+    }
+}
+
+/// Copies every allowlist entry from `source` to `destination`, replacing any entries on
+/// `destination` with the same device EUI, then reads the destination back to verify it.
+pub fn migrate(
+    source: &Token,
+    destination: &Token,
+    options: MigrationOptions,
+) -> Result<Migration, MtcapError> {
+    let gateway_response = curl::get(get_url(source, "loraNetwork/whitelist"))?;
+    let source_json = json::parse(&gateway_response)?["result"].clone();
+
+    let gateway_response = curl::get(get_url(destination, "loraNetwork/whitelist"))?;
+    let mut destination_json = json::parse(&gateway_response)?["result"].clone();
+
+    let mut migration = Migration {
+        devices: merge_allowlist(&mut destination_json, &source_json)?,
+        ..Default::default()
+    };
+    curl::put(
+        get_url(destination, "loraNetwork/whitelist"),
+        destination_json,
+    )?;
+
+    if options.copy_sessions {
+        for device_json in get_devices_json(source)?.members() {
+            let device_eui = Eui::from_str(&device_json["deveui"].to_string())?;
+            if !migration.devices.contains(&device_eui) || device_json["dev_addr"].is_empty() {
+                continue;
+            }
+
+            match create_session(destination, device_json) {
+                Ok(()) => migration.sessions_copied.push(device_eui),
+                Err(e) => migration.sessions_failed.push((device_eui, e)),
+            }
+        }
+    }
+
+    save_apply_and_wait(destination)?;
+
+    verify(destination, &source_json["devices"], &migration)?;
+
+    if options.remove_from_source {
+        devices::remove(source, &migration.devices)?;
+    }
+
+    Ok(migration)
+}
+
+fn verify(
+    destination: &Token,
+    source_devices_json: &json::JsonValue,
+    migration: &Migration,
+) -> Result<(), MtcapError> {
+    let gateway_response = curl::get(get_url(destination, "loraNetwork/whitelist"))?;
+    let allowlist_json = &json::parse(&gateway_response)?["result"]["devices"];
+    verify_allowlist(source_devices_json, allowlist_json, &migration.devices)?;
+
+    if !migration.sessions_copied.is_empty() {
+        let sessions = euis(&get_devices_json(destination)?)?;
+        if let Some(device_eui) = migration
+            .sessions_copied
+            .iter()
+            .find(|d| !sessions.contains(d))
+        {
+            return Err(MtcapError::Other(format!(
+                "{device_eui} is missing from the destination devices"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that each of `devices` is in the destination allowlist with the same fields as in the
+/// source.
+fn verify_allowlist(
+    source_devices_json: &json::JsonValue,
+    destination_devices_json: &json::JsonValue,
+    devices: &[Eui],
+) -> Result<(), MtcapError> {
+    for device_eui in devices {
+        let source_json = find(source_devices_json, device_eui).unwrap_or(&json::JsonValue::Null);
+        let Some(destination_json) = find(destination_devices_json, device_eui) else {
+            return Err(MtcapError::Other(format!(
+                "{device_eui} is missing from the destination allowlist"
+            )));
+        };
+
+        for field in VERIFIED_FIELDS {
+            if normalise(&source_json[field]) != normalise(&destination_json[field]) {
+                return Err(MtcapError::Other(format!(
+                    "{device_eui} has a different {field} on the destination"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn find<'a>(devices_json: &'a json::JsonValue, device_eui: &Eui) -> Option<&'a json::JsonValue> {
+    devices_json.members().find(|device_json| {
+        Eui::from_str(&device_json["deveui"].to_string()).is_ok_and(|d| d == *device_eui)
+    })
+}
+
+/// Ignores the case and separators with which the gateway may format EUIs and keys.
+fn normalise(json: &json::JsonValue) -> String {
+    json.to_string()
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' ' | ':'))
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Merges the source allowlist's entries into the destination's, and enables or disables the
+/// destination's allowlist as the source's is.
+fn merge_allowlist(
+    destination_json: &mut json::JsonValue,
+    source_json: &json::JsonValue,
+) -> Result<Vec<Eui>, MtcapError> {
+    let mut device_euis = Vec::new();
+
+    if let Some(enabled) = source_json["enabled"].as_bool() {
+        destination_json["enabled"] = enabled.into();
+    }
+
+    for device_json in source_json["devices"].members() {
+        let device_eui = Eui::from_str(&device_json["deveui"].to_string())?;
+
+        let mut included = false;
+        for existing_json in destination_json["devices"].members_mut() {
+            if Eui::from_str(&existing_json["deveui"].to_string())? == device_eui {
+                *existing_json = device_json.clone();
+                included = true;
+            }
+        }
+        if !included {
+            destination_json["devices"].push(device_json.clone())?;
+        }
+
+        device_euis.push(device_eui);
+    }
+
+    Ok(device_euis)
+}
+
+#[cfg(test)]
+#[path = "./test_migration.rs"]
+mod test_migration;
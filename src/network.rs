@@ -1,13 +1,36 @@
-use crate::credentials::{get_url, save_apply, Token};
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use strum_macros::Display;
+
+use crate::credentials::{get_url, save_apply, save_apply_and_wait, Address, Gateway, Token};
 use crate::curl;
+use crate::devices::{DeviceProfile, Eui};
 use crate::result::MtcapError;
 
+#[derive(Clone, Copy, Debug, Display, PartialEq)]
 pub enum Mode {
     NetworkServer,
     PacketForwarder,
     Disabled,
 }
 
+pub fn get_mode(token: &Token) -> Result<Mode, MtcapError> {
+    let response = curl::get(get_url(token, "loraNetwork/lora"))?;
+    let json = &json::parse(&response)?["result"];
+
+    let mode = if json["enabled"].as_bool() != Some(true) {
+        Mode::Disabled
+    } else if json["packetForwarderMode"].as_bool() == Some(true) {
+        Mode::PacketForwarder
+    } else {
+        Mode::NetworkServer
+    };
+
+    Ok(mode)
+}
+
 pub fn set_mode(token: &Token, mode: Mode) -> Result<(), MtcapError> {
     let response = curl::get(get_url(token, "loraNetwork/lora"))?;
 
@@ -25,7 +48,232 @@ pub fn set_mode(token: &Token, mode: Mode) -> Result<(), MtcapError> {
 
     curl::put(get_url(token, "loraNetwork/lora"), json)?;
 
-    save_apply(token)?;
+    save_apply_and_wait(token)?;
+
+    Ok(())
+}
+
+/// Where the gateway forwards packets in [`Mode::PacketForwarder`], using the Semtech UDP protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketForwarderConfig {
+    /// Host name or IP address of the network server.
+    pub server_address: String,
+    pub upstream_port: u16,
+    pub downstream_port: u16,
+    pub keepalive: Duration,
+    pub gateway_id: Eui,
+    pub channel_plan: DeviceProfile,
+    /// The sub-band of eight channels, 1 to 8, for US915 and AU915.
+    pub frequency_sub_band: Option<u8>,
+}
+
+impl PacketForwarderConfig {
+    fn from_json(json: &json::JsonValue) -> Result<Self, MtcapError> {
+        let forwarder = &json["packetForwarder"];
+        let port = |key: &str| {
+            forwarder[key]
+                .as_u16()
+                .ok_or_else(|| MtcapError::Other(format!("{key} is not a valid port")))
+        };
+
+        let channel_plan = json["channelPlan"].as_str().unwrap_or_default();
+
+        Ok(Self {
+            server_address: forwarder["serverAddress"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            upstream_port: port("upstreamPort")?,
+            downstream_port: port("downstreamPort")?,
+            keepalive: Duration::from_secs(
+                forwarder["keepAliveInterval"].as_u64().unwrap_or_default(),
+            ),
+            gateway_id: Eui::from_str(&forwarder["gatewayId"].to_string())?,
+            channel_plan: DeviceProfile::from_str(channel_plan)
+                .map_err(|_| MtcapError::Other(format!("Unknown channel plan {channel_plan}")))?,
+            frequency_sub_band: json["frequencySubBand"]
+                .as_u8()
+                .filter(|sub_band| (1..=8).contains(sub_band)),
+        })
+    }
+
+    fn update_json(&self, json: &mut json::JsonValue) {
+        let forwarder = &mut json["packetForwarder"];
+        forwarder["serverAddress"] = self.server_address.as_str().into();
+        forwarder["upstreamPort"] = self.upstream_port.into();
+        forwarder["downstreamPort"] = self.downstream_port.into();
+        forwarder["keepAliveInterval"] = self.keepalive.as_secs().into();
+        let gateway_id = format_like(&self.gateway_id, forwarder["gatewayId"].as_str());
+        forwarder["gatewayId"] = gateway_id.into();
+
+        json["channelPlan"] = self.channel_plan.to_string().into();
+        if let Some(sub_band) = self.frequency_sub_band {
+            json["frequencySubBand"] = sub_band.into();
+        }
+    }
+}
+
+/// Formats `eui` with the separator and case of `existing`, as the gateway stores it, for example
+/// `00800000a0001234`. Without an existing value, the gateway's usual plain form is used.
+fn format_like(eui: &Eui, existing: Option<&str>) -> String {
+    let existing = existing.unwrap_or_default();
+    let separator = existing
+        .chars()
+        .find(|c| !c.is_ascii_hexdigit())
+        .map(String::from)
+        .unwrap_or_default();
+
+    let formatted = eui.to_string().replace('-', &separator);
+    if existing.chars().any(|c| c.is_ascii_uppercase()) {
+        formatted.to_ascii_uppercase()
+    } else {
+        formatted
+    }
+}
+
+pub fn get_packet_forwarder(token: &Token) -> Result<PacketForwarderConfig, MtcapError> {
+    let response = curl::get(get_url(token, "loraNetwork/lora"))?;
+
+    PacketForwarderConfig::from_json(&json::parse(&response)?["result"])
+}
+
+/// Sets the packet forwarder configuration. This does not change the mode; see [`set_mode`].
+/// In packet forwarder mode the apply is not waited for, as there is no network server to answer.
+pub fn set_packet_forwarder(
+    token: &Token,
+    config: &PacketForwarderConfig,
+) -> Result<(), MtcapError> {
+    let response = curl::get(get_url(token, "loraNetwork/lora"))?;
+    let mut json = json::parse(&response)?["result"].clone();
+    config.update_json(&mut json);
+
+    curl::put(get_url(token, "loraNetwork/lora"), json)?;
+
+    save_apply_and_wait(token)?;
 
     Ok(())
 }
+
+const ETHERNET_API: &str = "ni/nis/eth0";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EthernetConfig {
+    Dhcp,
+    Static(StaticIpv4),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaticIpv4 {
+    pub address: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub gateway: Option<Ipv4Addr>,
+    /// Up to two DNS servers.
+    pub dns: Vec<Ipv4Addr>,
+}
+
+impl EthernetConfig {
+    fn from_json(json: &json::JsonValue) -> Result<Self, MtcapError> {
+        let ipv4 = &json["ipv4"];
+        let address = |key: &str| -> Result<Option<Ipv4Addr>, MtcapError> {
+            match ipv4[key].as_str().unwrap_or_default() {
+                "" => Ok(None),
+                address => address.parse().map(Some).map_err(|_| {
+                    MtcapError::Other(format!("{address} is not a valid {key} address"))
+                }),
+            }
+        };
+
+        let required = |key: &str| {
+            address(key)?.ok_or_else(|| {
+                MtcapError::Other(format!("Static ethernet configuration has no {key}"))
+            })
+        };
+
+        match ipv4["mode"].as_str().unwrap_or_default() {
+            "DHCP-CLIENT" => Ok(Self::Dhcp),
+            "STATIC" => Ok(Self::Static(StaticIpv4 {
+                address: required("ip")?,
+                netmask: required("mask")?,
+                gateway: address("gateway")?,
+                dns: [address("dns1")?, address("dns2")?]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            })),
+            mode => Err(MtcapError::Other(format!("Unknown ethernet mode {mode}"))),
+        }
+    }
+
+    fn update_json(&self, json: &mut json::JsonValue) -> Result<(), MtcapError> {
+        let ipv4 = &mut json["ipv4"];
+        match self {
+            Self::Dhcp => ipv4["mode"] = "DHCP-CLIENT".into(),
+            Self::Static(config) => {
+                if config.dns.len() > 2 {
+                    return Err(MtcapError::Other(
+                        "At most two DNS servers are supported".to_string(),
+                    ));
+                }
+
+                let optional =
+                    |address: Option<&Ipv4Addr>| address.map_or(String::new(), Ipv4Addr::to_string);
+                ipv4["mode"] = "STATIC".into();
+                ipv4["ip"] = config.address.to_string().into();
+                ipv4["mask"] = config.netmask.to_string().into();
+                ipv4["gateway"] = optional(config.gateway.as_ref()).into();
+                ipv4["dns1"] = optional(config.dns.first()).into();
+                ipv4["dns2"] = optional(config.dns.get(1)).into();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn get_ethernet(token: &Token) -> Result<EthernetConfig, MtcapError> {
+    let response = curl::get(get_url(token, ETHERNET_API))?;
+
+    EthernetConfig::from_json(&json::parse(&response)?["result"])
+}
+
+/// Sets the ethernet configuration, then saves and applies it without waiting, as the gateway may
+/// stop answering at its old address.
+///
+/// Returns the gateway at its new address, for [`crate::system::wait_for_login`]. The gateway is
+/// only moved to a new static address when it was reached at its old one; when it was reached by
+/// host name or through port forwarding, or with DHCP, it is returned unchanged.
+pub fn set_ethernet(
+    token: &Token,
+    gateway: &Gateway,
+    config: &EthernetConfig,
+) -> Result<Gateway, MtcapError> {
+    let response = curl::get(get_url(token, ETHERNET_API))?;
+    let mut json = json::parse(&response)?["result"].clone();
+    let old_address = json["ipv4"]["ip"].as_str().and_then(|ip| ip.parse().ok());
+    config.update_json(&mut json)?;
+
+    curl::put(get_url(token, ETHERNET_API), json)?;
+
+    save_apply(token)?;
+
+    Ok(gateway.relocated(new_address(gateway.address(), old_address, config)))
+}
+
+fn new_address(
+    address: &Address,
+    old_address: Option<Ipv4Addr>,
+    config: &EthernetConfig,
+) -> Address {
+    match config {
+        EthernetConfig::Static(config)
+            if old_address.is_some() && address.host().parse().ok() == old_address =>
+        {
+            address.clone().with_host(config.address.to_string())
+        }
+        _ => address.clone(),
+    }
+}
+
+#[cfg(test)]
+#[path = "./test_network.rs"]
+mod test_network;
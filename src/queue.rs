@@ -1,6 +1,8 @@
-use crate::credentials::{get_url, save_apply, Token};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+
+use crate::credentials::{get_url, save_apply_and_wait, Token};
 use crate::curl;
-use crate::devices::Eui;
+use crate::devices::{DeviceProfile, Eui};
 use crate::result::MtcapError;
 
 #[derive(Clone, Debug)]
@@ -11,6 +13,14 @@ pub struct Packet {
 }
 
 impl Packet {
+    pub fn new(device_eui: Eui, port: u8, payload: &[u8]) -> Self {
+        Self {
+            data: BASE64_STANDARD.encode(payload),
+            device_eui,
+            port,
+        }
+    }
+
     pub fn device_eui(&self) -> &Eui {
         &self.device_eui
     }
@@ -22,6 +32,12 @@ impl Packet {
     pub fn data(&self) -> &str {
         &self.data
     }
+
+    pub fn payload(&self) -> Result<Vec<u8>, MtcapError> {
+        BASE64_STANDARD
+            .decode(&self.data)
+            .map_err(|e| MtcapError::Other(e.to_string()))
+    }
 }
 
 pub fn get(token: &Token) -> Result<Vec<Packet>, MtcapError> {
@@ -40,12 +56,44 @@ pub fn get(token: &Token) -> Result<Vec<Packet>, MtcapError> {
     Ok(packets)
 }
 
+/// Queues a downlink, first checking its size against the device's region and, where the gateway
+/// reports it, the RX1 and RX2 data rates following the device's last uplink.
+///
+/// The size is not checked for devices whose device profile is not one of the built-in
+/// `LW102-OTA-*` profiles.
+pub fn add(token: &Token, packet: &Packet) -> Result<(), MtcapError> {
+    let gateway_response = curl::get(get_url(
+        token,
+        format!("lora/devices/{}", packet.device_eui),
+    ))?;
+    let device_json = &json::parse(&gateway_response)?["result"];
+
+    if let Some(device_profile) =
+        DeviceProfile::from_id(&device_json["device_profile_id"].to_string())
+    {
+        let regional_parameters = device_profile.regional_parameters();
+        let data_rate = device_json["datr"]
+            .as_str()
+            .and_then(|datr| regional_parameters.find_data_rate(datr));
+        regional_parameters.check_downlink_payload_size(data_rate, packet.payload()?.len())?;
+    }
+
+    let packet_json = json::object! {
+        deveui: packet.device_eui.to_string(),
+        data: packet.data.clone(),
+        port: packet.port,
+    };
+    curl::post_json(get_url(token, "lora/packets/queue"), packet_json)?;
+
+    Ok(())
+}
+
 pub fn remove(token: &Token, device_euis: &[Eui]) -> Result<(), MtcapError> {
     for device_eui in device_euis {
         curl::delete(get_url(token, format!("lora/packets/queue/{device_eui}")))?;
     }
 
-    save_apply(token)?;
+    save_apply_and_wait(token)?;
 
     Ok(())
 }
@@ -0,0 +1,391 @@
+use crate::devices::DeviceProfile;
+use crate::result::MtcapError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Modulation {
+    /// Bandwidth is in Hz.
+    Lora {
+        spreading_factor: u8,
+        bandwidth: u32,
+    },
+    /// Bit rate is in bit/s.
+    Fsk { bit_rate: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DataRate {
+    pub modulation: Modulation,
+    /// Maximum FRMPayload size in bytes (N), without a repeater and with dwell time limits off.
+    pub max_payload_size: u8,
+}
+
+/// A transmit duty cycle limit, applying to frequencies from `min_frequency` to `max_frequency` Hz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DutyCycleBand {
+    pub min_frequency: u32,
+    pub max_frequency: u32,
+    pub duty_cycle: f64,
+}
+
+/// LoRaWAN regional parameters for a [`DeviceProfile`]. Frequencies are in Hz.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionalParameters {
+    uplink_channels: Vec<u32>,
+    downlink_channels: Vec<u32>,
+    data_rates: Vec<Option<DataRate>>,
+    /// The RX1 downlink data rate for each uplink data rate, at RX1DROffset 0. Where empty, RX1
+    /// uses the uplink data rate.
+    rx1_data_rates: Vec<u8>,
+    rx2_frequency: u32,
+    rx2_data_rate: u8,
+    duty_cycle_bands: Vec<DutyCycleBand>,
+}
+
+impl RegionalParameters {
+    pub(crate) fn new(device_profile: &DeviceProfile) -> Self {
+        match device_profile {
+            DeviceProfile::As923 => as923(0),
+            DeviceProfile::As923_2 => as923(-1_800_000),
+            DeviceProfile::As923_3 => as923(-6_600_000),
+            DeviceProfile::As923_4 => as923(-5_900_000),
+            DeviceProfile::Au915 => Self {
+                uplink_channels: channels(915_200_000, 200_000, 64)
+                    .chain(channels(915_900_000, 1_600_000, 8))
+                    .collect(),
+                downlink_channels: channels(923_300_000, 600_000, 8).collect(),
+                data_rates: vec![
+                    lora(12, 125_000, 51),
+                    lora(11, 125_000, 51),
+                    lora(10, 125_000, 51),
+                    lora(9, 125_000, 115),
+                    lora(8, 125_000, 242),
+                    lora(7, 125_000, 242),
+                    lora(8, 500_000, 242),
+                    None,
+                    lora(12, 500_000, 53),
+                    lora(11, 500_000, 129),
+                    lora(10, 500_000, 242),
+                    lora(9, 500_000, 242),
+                    lora(8, 500_000, 242),
+                    lora(7, 500_000, 242),
+                ],
+                rx1_data_rates: vec![8, 9, 10, 11, 12, 13, 13],
+                rx2_frequency: 923_300_000,
+                rx2_data_rate: 8,
+                duty_cycle_bands: Vec::new(),
+            },
+            DeviceProfile::Cn470 => Self {
+                uplink_channels: channels(470_300_000, 200_000, 96).collect(),
+                downlink_channels: channels(500_300_000, 200_000, 48).collect(),
+                data_rates: vec![
+                    lora(12, 125_000, 51),
+                    lora(11, 125_000, 51),
+                    lora(10, 125_000, 51),
+                    lora(9, 125_000, 115),
+                    lora(8, 125_000, 242),
+                    lora(7, 125_000, 242),
+                ],
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 505_300_000,
+                rx2_data_rate: 0,
+                duty_cycle_bands: Vec::new(),
+            },
+            DeviceProfile::Cn779 => Self {
+                uplink_channels: vec![779_500_000, 779_700_000, 779_900_000],
+                downlink_channels: Vec::new(),
+                data_rates: eu_data_rates(),
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 786_000_000,
+                rx2_data_rate: 0,
+                duty_cycle_bands: vec![band(779_000_000, 787_000_000, 0.01)],
+            },
+            DeviceProfile::Eu433 => Self {
+                uplink_channels: vec![433_175_000, 433_375_000, 433_575_000],
+                downlink_channels: Vec::new(),
+                data_rates: eu_data_rates(),
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 434_665_000,
+                rx2_data_rate: 0,
+                duty_cycle_bands: vec![band(433_050_000, 434_790_000, 0.01)],
+            },
+            DeviceProfile::Eu868 => Self {
+                uplink_channels: vec![868_100_000, 868_300_000, 868_500_000],
+                downlink_channels: Vec::new(),
+                data_rates: eu_data_rates(),
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 869_525_000,
+                rx2_data_rate: 0,
+                duty_cycle_bands: vec![
+                    band(863_000_000, 865_000_000, 0.001),
+                    band(865_000_000, 868_000_000, 0.01),
+                    band(868_000_000, 868_600_000, 0.01),
+                    band(868_700_000, 869_200_000, 0.001),
+                    band(869_400_000, 869_650_000, 0.1),
+                    band(869_700_000, 870_000_000, 0.01),
+                ],
+            },
+            DeviceProfile::In865 => Self {
+                uplink_channels: vec![865_062_500, 865_402_500, 865_985_000],
+                downlink_channels: Vec::new(),
+                data_rates: vec![
+                    lora(12, 125_000, 51),
+                    lora(11, 125_000, 51),
+                    lora(10, 125_000, 51),
+                    lora(9, 125_000, 115),
+                    lora(8, 125_000, 242),
+                    lora(7, 125_000, 242),
+                    None,
+                    fsk(50_000, 242),
+                ],
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 866_550_000,
+                rx2_data_rate: 2,
+                duty_cycle_bands: Vec::new(),
+            },
+            DeviceProfile::Kr920 => Self {
+                uplink_channels: vec![922_100_000, 922_300_000, 922_500_000],
+                downlink_channels: Vec::new(),
+                data_rates: vec![
+                    lora(12, 125_000, 51),
+                    lora(11, 125_000, 51),
+                    lora(10, 125_000, 51),
+                    lora(9, 125_000, 115),
+                    lora(8, 125_000, 242),
+                    lora(7, 125_000, 242),
+                ],
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 921_900_000,
+                rx2_data_rate: 0,
+                duty_cycle_bands: Vec::new(),
+            },
+            DeviceProfile::Ru864 => Self {
+                uplink_channels: vec![868_900_000, 869_100_000],
+                downlink_channels: Vec::new(),
+                data_rates: eu_data_rates(),
+                rx1_data_rates: Vec::new(),
+                rx2_frequency: 869_100_000,
+                rx2_data_rate: 0,
+                duty_cycle_bands: vec![band(864_000_000, 870_000_000, 0.01)],
+            },
+            DeviceProfile::Us915 => Self {
+                uplink_channels: channels(902_300_000, 200_000, 64)
+                    .chain(channels(903_000_000, 1_600_000, 8))
+                    .collect(),
+                downlink_channels: channels(923_300_000, 600_000, 8).collect(),
+                data_rates: vec![
+                    lora(10, 125_000, 11),
+                    lora(9, 125_000, 53),
+                    lora(8, 125_000, 125),
+                    lora(7, 125_000, 242),
+                    lora(8, 500_000, 242),
+                    None,
+                    None,
+                    None,
+                    lora(12, 500_000, 53),
+                    lora(11, 500_000, 129),
+                    lora(10, 500_000, 242),
+                    lora(9, 500_000, 242),
+                    lora(8, 500_000, 242),
+                    lora(7, 500_000, 242),
+                ],
+                rx1_data_rates: vec![10, 11, 12, 13, 13],
+                rx2_frequency: 923_300_000,
+                rx2_data_rate: 8,
+                duty_cycle_bands: Vec::new(),
+            },
+        }
+    }
+
+    /// The default uplink channels.
+    pub fn uplink_channels(&self) -> &[u32] {
+        &self.uplink_channels
+    }
+
+    /// The downlink channels. Where empty, downlinks use the uplink channels.
+    pub fn downlink_channels(&self) -> &[u32] {
+        &self.downlink_channels
+    }
+
+    /// The data rates, indexed by data rate number. Reserved data rates are `None`.
+    pub fn data_rates(&self) -> &[Option<DataRate>] {
+        &self.data_rates
+    }
+
+    pub fn data_rate(&self, data_rate: u8) -> Option<&DataRate> {
+        self.data_rates.get(data_rate as usize)?.as_ref()
+    }
+
+    /// The maximum FRMPayload size in bytes at the given data rate.
+    pub fn max_payload_size(&self, data_rate: u8) -> Option<usize> {
+        self.data_rate(data_rate)
+            .map(|data_rate| data_rate.max_payload_size as usize)
+    }
+
+    /// Checks that a payload fits at the given data rate or, where the data rate is unknown, at the
+    /// fastest data rate of the region.
+    pub fn check_payload_size(
+        &self,
+        data_rate: Option<u8>,
+        length: usize,
+    ) -> Result<(), MtcapError> {
+        let max_length = match data_rate {
+            Some(data_rate) => self.max_payload_size(data_rate).ok_or_else(|| {
+                MtcapError::Other(format!("Data rate {data_rate} is not valid in this region"))
+            })?,
+            None => self
+                .data_rates
+                .iter()
+                .flatten()
+                .map(|data_rate| data_rate.max_payload_size as usize)
+                .max()
+                .unwrap_or(0),
+        };
+
+        if length > max_length {
+            Err(MtcapError::PayloadTooLarge { length, max_length })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The RX1 downlink data rate for an uplink at `uplink_data_rate`, at RX1DROffset 0.
+    pub fn rx1_data_rate(&self, uplink_data_rate: u8) -> Option<u8> {
+        if self.rx1_data_rates.is_empty() {
+            self.data_rate(uplink_data_rate).map(|_| uplink_data_rate)
+        } else {
+            self.rx1_data_rates.get(uplink_data_rate as usize).copied()
+        }
+    }
+
+    /// Checks that a downlink fits in the RX1 or the RX2 window following an uplink at
+    /// `uplink_data_rate` or, where that is unknown, at the fastest data rate of the region.
+    pub fn check_downlink_payload_size(
+        &self,
+        uplink_data_rate: Option<u8>,
+        length: usize,
+    ) -> Result<(), MtcapError> {
+        let Some(uplink_data_rate) = uplink_data_rate else {
+            return self.check_payload_size(None, length);
+        };
+
+        let max_length = self
+            .rx1_data_rate(uplink_data_rate)
+            .into_iter()
+            .chain([self.rx2_data_rate])
+            .filter_map(|data_rate| self.max_payload_size(data_rate))
+            .max()
+            .unwrap_or(0);
+
+        if length > max_length {
+            Err(MtcapError::PayloadTooLarge { length, max_length })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Finds the data rate number of a LoRa data rate given as e.g. `SF7BW125`.
+    pub fn find_data_rate(&self, datr: &str) -> Option<u8> {
+        let (spreading_factor, bandwidth) = datr.strip_prefix("SF")?.split_once("BW")?;
+        let modulation = Modulation::Lora {
+            spreading_factor: spreading_factor.parse().ok()?,
+            bandwidth: bandwidth.parse::<u32>().ok()? * 1000,
+        };
+
+        self.data_rates
+            .iter()
+            .position(|data_rate| {
+                data_rate.is_some_and(|data_rate| data_rate.modulation == modulation)
+            })
+            .map(|index| index as u8)
+    }
+
+    pub fn rx2_frequency(&self) -> u32 {
+        self.rx2_frequency
+    }
+
+    pub fn rx2_data_rate(&self) -> u8 {
+        self.rx2_data_rate
+    }
+
+    /// The duty cycle limits. Where empty, the region has no duty cycle limit.
+    pub fn duty_cycle_bands(&self) -> &[DutyCycleBand] {
+        &self.duty_cycle_bands
+    }
+
+    pub fn duty_cycle(&self, frequency: u32) -> Option<f64> {
+        self.duty_cycle_bands
+            .iter()
+            .find(|band| band.min_frequency <= frequency && frequency <= band.max_frequency)
+            .map(|band| band.duty_cycle)
+    }
+}
+
+/// AS923 with its channels moved by `offset`. The duty cycle band is regulatory, so it stays put.
+fn as923(offset: i32) -> RegionalParameters {
+    let shift = |frequency: u32| frequency.checked_add_signed(offset).unwrap();
+
+    RegionalParameters {
+        uplink_channels: vec![shift(923_200_000), shift(923_400_000)],
+        downlink_channels: Vec::new(),
+        data_rates: vec![
+            lora(12, 125_000, 51),
+            lora(11, 125_000, 51),
+            lora(10, 125_000, 51),
+            lora(9, 125_000, 115),
+            lora(8, 125_000, 242),
+            lora(7, 125_000, 242),
+            lora(7, 250_000, 242),
+            fsk(50_000, 242),
+        ],
+        rx1_data_rates: Vec::new(),
+        rx2_frequency: shift(923_200_000),
+        rx2_data_rate: 2,
+        duty_cycle_bands: vec![band(915_000_000, 928_000_000, 0.01)],
+    }
+}
+
+fn eu_data_rates() -> Vec<Option<DataRate>> {
+    vec![
+        lora(12, 125_000, 51),
+        lora(11, 125_000, 51),
+        lora(10, 125_000, 51),
+        lora(9, 125_000, 115),
+        lora(8, 125_000, 242),
+        lora(7, 125_000, 242),
+        lora(7, 250_000, 242),
+        fsk(50_000, 242),
+    ]
+}
+
+fn channels(first: u32, spacing: u32, count: u32) -> impl Iterator<Item = u32> {
+    (0..count).map(move |i| first + i * spacing)
+}
+
+fn lora(spreading_factor: u8, bandwidth: u32, max_payload_size: u8) -> Option<DataRate> {
+    Some(DataRate {
+        modulation: Modulation::Lora {
+            spreading_factor,
+            bandwidth,
+        },
+        max_payload_size,
+    })
+}
+
+fn fsk(bit_rate: u32, max_payload_size: u8) -> Option<DataRate> {
+    Some(DataRate {
+        modulation: Modulation::Fsk { bit_rate },
+        max_payload_size,
+    })
+}
+
+fn band(min_frequency: u32, max_frequency: u32, duty_cycle: f64) -> DutyCycleBand {
+    DutyCycleBand {
+        min_frequency,
+        max_frequency,
+        duty_cycle,
+    }
+}
+
+#[cfg(test)]
+#[path = "./test_region.rs"]
+mod test_region;
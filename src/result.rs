@@ -10,6 +10,16 @@ pub enum MtcapError {
     Json(#[from] json::Error),
     #[error(transparent)]
     ParseIntError(#[from] std::num::ParseIntError),
+    #[error("MIC does not match")]
+    InvalidMic,
+    #[error("Invalid frame: {0}")]
+    InvalidFrame(String),
+    #[error("Payload of {length} bytes exceeds the maximum of {max_length} bytes")]
+    PayloadTooLarge { length: usize, max_length: usize },
+    #[error("Timed out waiting for {0}")]
+    Timeout(String),
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
     #[error("{0}")]
     Other(String),
 }
@@ -20,6 +30,15 @@ impl From<MtcapError> for io::Error {
             MtcapError::Io(e) => e,
             MtcapError::Json(inner) => io::Error::new(io::ErrorKind::Other, inner),
             MtcapError::ParseIntError(inner) => io::Error::new(io::ErrorKind::Other, inner),
+            MtcapError::InvalidMic => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+            MtcapError::InvalidFrame(inner) => io::Error::new(io::ErrorKind::InvalidData, inner),
+            MtcapError::PayloadTooLarge { .. } => {
+                io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+            }
+            MtcapError::Timeout(_) => io::Error::new(io::ErrorKind::TimedOut, err.to_string()),
+            MtcapError::ChecksumMismatch { .. } => {
+                io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+            }
             MtcapError::Other(inner) => io::Error::new(io::ErrorKind::Other, inner),
         }
     }
@@ -0,0 +1,260 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::credentials::{get_url, login, logout, Gateway, Token};
+use crate::curl;
+use crate::devices::DeviceProfile;
+use crate::result::MtcapError;
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemInfo {
+    product_id: String,
+    serial_number: String,
+    firmware_version: String,
+    hardware_version: String,
+    uptime: Option<Duration>,
+    mac_addresses: Vec<String>,
+    lora_card: Option<String>,
+    frequency_band: Option<u16>,
+}
+
+impl SystemInfo {
+    /// For example `MTCAP-868-001A`.
+    pub fn product_id(&self) -> &str {
+        &self.product_id
+    }
+
+    pub fn serial_number(&self) -> &str {
+        &self.serial_number
+    }
+
+    pub fn firmware_version(&self) -> &str {
+        &self.firmware_version
+    }
+
+    pub fn hardware_version(&self) -> &str {
+        &self.hardware_version
+    }
+
+    /// The uptime, if the gateway reported it in a recognised format.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.uptime
+    }
+
+    pub fn mac_addresses(&self) -> &[String] {
+        &self.mac_addresses
+    }
+
+    /// The product ID of the LoRa accessory card, where the gateway has one.
+    pub fn lora_card(&self) -> Option<&str> {
+        self.lora_card.as_deref()
+    }
+
+    /// The LoRa frequency band in MHz, taken from the product ID, for example 868 or 915.
+    pub fn frequency_band(&self) -> Option<u16> {
+        self.frequency_band
+    }
+
+    /// The usual device profile for the frequency band. 915 MHz hardware may also be used for
+    /// AU915, and 923 MHz hardware for the other AS923 variants.
+    pub fn device_profile(&self) -> Option<DeviceProfile> {
+        match self.frequency_band? {
+            433 => Some(DeviceProfile::Eu433),
+            470 => Some(DeviceProfile::Cn470),
+            779 => Some(DeviceProfile::Cn779),
+            864 => Some(DeviceProfile::Ru864),
+            865 | 866 => Some(DeviceProfile::In865),
+            868 => Some(DeviceProfile::Eu868),
+            915 => Some(DeviceProfile::Us915),
+            920 => Some(DeviceProfile::Kr920),
+            923 => Some(DeviceProfile::As923),
+            _ => None,
+        }
+    }
+
+    fn from_json(json: &json::JsonValue) -> Self {
+        let string = |key: &str| json[key].as_str().unwrap_or_default().to_string();
+
+        let mac_addresses = json
+            .entries()
+            .filter(|(key, _)| key.starts_with("macAddress"))
+            .filter_map(|(_, value)| value.as_str())
+            .filter(|mac_address| !mac_address.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let lora_card = json["accessoryCards"]
+            .members()
+            .filter_map(|card| card["productId"].as_str().or(card["product-id"].as_str()))
+            .find(|product_id| product_id.to_ascii_uppercase().contains("LORA"))
+            .map(str::to_string);
+
+        let product_id = string("productId");
+        let frequency_band = lora_card
+            .as_deref()
+            .and_then(parse_frequency_band)
+            .or_else(|| parse_frequency_band(&product_id));
+
+        Self {
+            serial_number: string("serialNumber"),
+            firmware_version: string("firmware"),
+            hardware_version: string("hardwareVersion"),
+            uptime: parse_uptime(&json["uptime"]),
+            mac_addresses,
+            lora_card,
+            frequency_band,
+            product_id,
+        }
+    }
+}
+
+pub fn info(token: &Token) -> Result<SystemInfo, MtcapError> {
+    let gateway_response = curl::get(get_url(token, "system"))?;
+    let system_json = &json::parse(&gateway_response)?["result"];
+
+    Ok(SystemInfo::from_json(system_json))
+}
+
+/// Reboots the gateway, without waiting for it to come back.
+pub fn reboot(token: &Token) -> Result<(), MtcapError> {
+    curl::post(get_url(token, "command/restart"))?;
+
+    Ok(())
+}
+
+/// Reboots the gateway, waits until it has gone down and come back, and logs in again.
+pub fn reboot_and_wait(
+    gateway: &Gateway,
+    token: &Token,
+    timeout: Duration,
+) -> Result<Token, MtcapError> {
+    let deadline = Instant::now() + timeout;
+
+    reboot(token)?;
+
+    poll_until(deadline, "the gateway to go down", || is_down(gateway))?;
+    wait_for_login(gateway, deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Restarts the LoRa network server, without waiting for it to come back.
+pub fn restart_lora(token: &Token) -> Result<(), MtcapError> {
+    curl::post(get_url(token, "command/lora_restart"))?;
+
+    Ok(())
+}
+
+/// Restarts the LoRa network server, waits until it answers again, and logs in again, logging
+/// out of `token`.
+pub fn restart_lora_and_wait(
+    gateway: &Gateway,
+    token: &Token,
+    timeout: Duration,
+) -> Result<Token, MtcapError> {
+    let deadline = Instant::now() + timeout;
+
+    restart_lora(token)?;
+
+    thread::sleep(POLL_INTERVAL);
+    let new_token = wait_for_login(gateway, deadline.saturating_duration_since(Instant::now()))?;
+    let _ = logout(token);
+    wait_for_lora(
+        &new_token,
+        deadline.saturating_duration_since(Instant::now()),
+    )?;
+
+    Ok(new_token)
+}
+
+/// Whether the gateway refuses logins, logging out again if it does not.
+pub(crate) fn is_down(gateway: &Gateway) -> bool {
+    match login(gateway) {
+        Ok(probe) => {
+            let _ = logout(&probe);
+            false
+        }
+        Err(_) => true,
+    }
+}
+
+/// Polls until login succeeds.
+pub fn wait_for_login(gateway: &Gateway, timeout: Duration) -> Result<Token, MtcapError> {
+    let mut token = None;
+    poll_until(Instant::now() + timeout, "login", || {
+        token = login(gateway).ok();
+        token.is_some()
+    })?;
+
+    Ok(token.unwrap())
+}
+
+/// Polls until the LoRa network server answers.
+pub fn wait_for_lora(token: &Token, timeout: Duration) -> Result<(), MtcapError> {
+    poll_until(Instant::now() + timeout, "the LoRa network server", || {
+        lora_answers(token)
+    })
+}
+
+pub(crate) fn lora_answers(token: &Token) -> bool {
+    curl::get(get_url(token, "lora/packets/queue")).is_ok()
+}
+
+pub(crate) fn poll_until<F: FnMut() -> bool>(
+    deadline: Instant,
+    description: &str,
+    mut condition: F,
+) -> Result<(), MtcapError> {
+    loop {
+        if condition() {
+            return Ok(());
+        }
+        if Instant::now() + POLL_INTERVAL > deadline {
+            return Err(MtcapError::Timeout(description.to_string()));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn parse_frequency_band(product_id: &str) -> Option<u16> {
+    product_id
+        .split('-')
+        .filter_map(|part| part.parse::<u16>().ok())
+        .find(|band| (400..1000).contains(band))
+}
+
+/// Parses an uptime given in seconds, or as `[D days, ]HH:MM[:SS]`.
+fn parse_uptime(json: &json::JsonValue) -> Option<Duration> {
+    if let Some(seconds) = json.as_u64() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let uptime = json.as_str()?.trim();
+    if let Ok(seconds) = uptime.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let (days, time) = match uptime.split_once(',') {
+        Some((days, time)) => {
+            let days = days.split_whitespace().next()?.parse::<u64>().ok()?;
+            (days, time.trim())
+        }
+        None => (0, uptime),
+    };
+
+    let mut parts = time.split(':').map(|part| part.parse::<u64>().ok());
+    let hours = parts.next()??;
+    let minutes = parts.next()??;
+    let seconds = parts.next().unwrap_or(Some(0))?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(Duration::from_secs(
+        ((days * 24 + hours) * 60 + minutes) * 60 + seconds,
+    ))
+}
+
+#[cfg(test)]
+#[path = "./test_system.rs"]
+mod test_system;
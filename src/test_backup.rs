@@ -0,0 +1,78 @@
+use super::*;
+
+fn allowlist_entry(device_eui: &str, class: &str) -> json::JsonValue {
+    json::object! {
+        deveui: device_eui,
+        appeui: "00-00-00-00-00-00-00-01",
+        appkey: "000102030405060708090a0b0c0d0e0f",
+        class: class,
+        device_profile_id: "LW102-OTA-EU868",
+        network_profile_id: "DEFAULT-CLASS-A",
+    }
+}
+
+fn snapshot(devices: Vec<json::JsonValue>) -> LoraSnapshot {
+    LoraSnapshot::from_json(&json::object! {
+        lora: { enabled: true, packetForwarderMode: false },
+        allowlist: { enabled: true, devices: devices },
+        device_profiles: [{ id: "LW102-OTA-EU868" }],
+        network_profiles: [{ id: "DEFAULT-CLASS-A" }],
+    })
+}
+
+#[test]
+fn json_round_trip() {
+    let before = snapshot(vec![
+        allowlist_entry("00-00-00-00-00-00-00-02", "A"),
+        allowlist_entry("00-00-00-00-00-00-00-01", "C"),
+    ]);
+
+    let after = LoraSnapshot::from_json_string(&before.to_json_string()).unwrap();
+    assert!(before.diff(&after).is_empty());
+    assert_eq!(after.allowlist()[0]["deveui"], "00-00-00-00-00-00-00-01");
+}
+
+#[test]
+fn diff() {
+    let before = snapshot(vec![
+        allowlist_entry("00-00-00-00-00-00-00-01", "A"),
+        allowlist_entry("00-00-00-00-00-00-00-02", "A"),
+    ]);
+    let after = snapshot(vec![
+        allowlist_entry("00-00-00-00-00-00-00-02", "C"),
+        allowlist_entry("00-00-00-00-00-00-00-03", "A"),
+    ]);
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.devices_added, [Eui::new([0, 0, 0, 0, 0, 0, 0, 3])]);
+    assert_eq!(diff.devices_removed, [Eui::new([0, 0, 0, 0, 0, 0, 0, 1])]);
+    assert_eq!(diff.devices_changed, [Eui::new([0, 0, 0, 0, 0, 0, 0, 2])]);
+    assert!(!diff.lora_changed);
+}
+
+#[test]
+fn unrecognised_entries_kept() {
+    let mut entry = allowlist_entry("00-00-00-00-00-00-00-01", "A");
+    entry["device_profile_id"] = "CUSTOM".into();
+    entry["note"] = "meter 12".into();
+
+    let before = snapshot(vec![entry.clone()]);
+    let after = LoraSnapshot::from_json_string(&before.to_json_string()).unwrap();
+    assert_eq!(after.allowlist()[0], entry);
+}
+
+#[test]
+fn profile_changes() {
+    let current = json::array![
+        { id: "kept", v: 1 },
+        { id: "stale" },
+        { id: "LW102-OTA-AS923" },
+        { id: "DEFAULT-CLASS-B" },
+    ];
+    let wanted = json::array![{ id: "kept", v: 2 }, { id: "missing" }];
+
+    let changes = ProfileChanges::new(&current, &wanted);
+    assert_eq!(changes.update, [&wanted[0]]);
+    assert_eq!(changes.create, [&wanted[1]]);
+    assert_eq!(changes.delete, ["stale"]);
+}
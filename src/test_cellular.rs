@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn status_from_json() {
+    let status = CellularStatus::from_json(&json::object! {
+        rssidBm: "-73",
+        rsrp: -101,
+        network: "Vodafone",
+        netReg: "ROAMING",
+        imei: "351234567890123",
+        iccid: "8944123456789012345",
+        rxBytes: 123456,
+        txBytes: "7890",
+    });
+
+    assert_eq!(status.rssi(), Some(-73));
+    assert_eq!(status.rsrp(), Some(-101));
+    assert_eq!(status.carrier(), "Vodafone");
+    assert_eq!(status.registration(), Registration::Roaming);
+    assert_eq!(status.imei(), "351234567890123");
+    assert_eq!(status.iccid(), "8944123456789012345");
+    assert_eq!(status.bytes_received(), Some(123456));
+    assert_eq!(status.bytes_sent(), Some(7890));
+
+    let status = CellularStatus::from_json(&json::object! { netReg: "Not Registered" });
+    assert_eq!(status.rssi(), None);
+    assert_eq!(status.registration(), Registration::NotRegistered);
+}
+
+#[test]
+fn config_round_trip() {
+    let mut json = json::object! {
+        enabled: false,
+        apn: "",
+        authType: "NONE",
+        username: "",
+        password: "",
+        dialOnDemand: false,
+    };
+    let config = CellularConfig {
+        enabled: true,
+        apn: "internet.example".to_string(),
+        authentication: Authentication::PapChap,
+        username: "user".to_string(),
+        password: "secret".to_string(),
+    };
+
+    config.update_json(&mut json);
+    assert_eq!(json["authType"], "PAP-CHAP");
+    assert_eq!(json["dialOnDemand"], false);
+    assert_eq!(CellularConfig::from_json(&json).unwrap(), config);
+}
@@ -0,0 +1,37 @@
+use chrono::TimeZone;
+
+use super::*;
+
+#[test]
+fn time_from_json() {
+    let time = GatewayTime::from_json(&json::object! {
+        dateTime: "2024-03-05T10:11:12+01:00",
+        timeZone: "Europe/Berlin",
+    })
+    .unwrap();
+
+    assert_eq!(
+        time.time(),
+        Utc.with_ymd_and_hms(2024, 3, 5, 9, 11, 12).unwrap()
+    );
+    assert_eq!(time.time_zone(), "Europe/Berlin");
+
+    assert!(GatewayTime::from_json(&json::object! { timeZone: "UTC" }).is_err());
+}
+
+#[test]
+fn ntp_round_trip() {
+    let mut json = json::object! {
+        enabled: false,
+        servers: ["", ""],
+        pollingTime: 15,
+    };
+    let config = NtpConfig {
+        enabled: true,
+        servers: vec!["0.pool.ntp.org".to_string(), "1.pool.ntp.org".to_string()],
+    };
+
+    config.update_json(&mut json);
+    assert_eq!(json["pollingTime"], 15);
+    assert_eq!(NtpConfig::from_json(&json), config);
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+fn gateway() -> Gateway {
+    Gateway::new(
+        [192, 168, 2, 1],
+        "admin".to_string(),
+        "Secret#1".to_string(),
+    )
+}
+
+#[test]
+fn answer_steps() {
+    let challenge = json::object! { aasID: "1", aasType: "password", aasMsg: "Set a password" };
+    assert_eq!(
+        answer(&challenge, &gateway()).unwrap(),
+        Some(json::object! { username: "admin", aasID: "1", aasAnswer: "Secret#1" })
+    );
+
+    let challenge = json::object! { aasID: "2", aasType: "password confirm" };
+    assert_eq!(
+        answer(&challenge, &gateway()).unwrap().unwrap()["aasAnswer"],
+        "Secret#1"
+    );
+
+    let challenge = json::object! { aasID: "3", aasType: "info", aasMsg: "Password set" };
+    assert_eq!(
+        answer(&challenge, &gateway()).unwrap().unwrap()["aasAnswer"],
+        ""
+    );
+
+    let challenge = json::object! { aasID: "4", aasType: "info", aasDone: true };
+    assert_eq!(answer(&challenge, &gateway()).unwrap(), None);
+    assert_eq!(answer(&json::object! {}, &gateway()).unwrap(), None);
+
+    let challenge = json::object! { aasID: "5", aasType: "captcha", aasMsg: "Type the text" };
+    assert!(answer(&challenge, &gateway()).is_err());
+}
@@ -0,0 +1,72 @@
+use super::*;
+
+#[test]
+fn address_from_str() {
+    let address = Address::from_str("192.168.2.1").unwrap();
+    assert_eq!(address, Address::new("192.168.2.1"));
+    assert_eq!(address.to_string(), "https://192.168.2.1");
+
+    let address = Address::from_str("gateway.example.com:8443").unwrap();
+    assert_eq!(address.host(), "gateway.example.com");
+    assert_eq!(address.port(), Some(8443));
+    assert_eq!(address.to_string(), "https://gateway.example.com:8443");
+
+    let address = Address::from_str("http://10.0.0.1:8080/site-a/").unwrap();
+    assert_eq!(
+        address,
+        Address::new("10.0.0.1")
+            .with_scheme("http")
+            .with_port(8080)
+            .with_base_path("site-a")
+    );
+    assert_eq!(address.to_string(), "http://10.0.0.1:8080/site-a");
+
+    let address = Address::from_str("[fd00::1]:8443").unwrap();
+    assert_eq!(address.host(), "fd00::1");
+    assert_eq!(address.to_string(), "https://[fd00::1]:8443");
+    assert_eq!(Address::from_str("fd00::1").unwrap().port(), None);
+
+    assert!(Address::from_str("").is_err());
+    assert!(Address::from_str("host:port").is_err());
+    assert!(Address::from_str("[fd00::1").is_err());
+    assert!(Address::from_str("fd00::xyz").is_err());
+}
+
+#[test]
+fn url() {
+    let gateway = Gateway::new([192, 168, 2, 1], String::new(), String::new());
+    assert_eq!(gateway.address().to_string(), "https://192.168.2.1");
+
+    let token = Token::new(
+        Address::from_str("[fd00::1]:8443/proxy").unwrap(),
+        "abc".to_string(),
+    );
+    assert_eq!(
+        get_url(&token, "lora/devices"),
+        "https://[fd00::1]:8443/proxy/api/lora/devices?token=abc"
+    );
+}
+
+#[test]
+fn login_url_encoding() {
+    let gateway = Gateway::new(
+        [192, 168, 2, 1],
+        "admin user".to_string(),
+        "p&ss#w+rd%=1".to_string(),
+    );
+    assert_eq!(
+        login_url(&gateway),
+        "https://192.168.2.1/api/login?username=admin%20user&password=p%26ss%23w%2Brd%25%3D1"
+    );
+    assert_eq!(percent_encode("a-Z_0.9~"), "a-Z_0.9~");
+    assert_eq!(percent_encode("é/"), "%C3%A9%2F");
+}
+
+#[test]
+fn apply_timeout() {
+    let token = Token::new(Address::new("192.168.2.1"), "token".to_string());
+    assert_eq!(token.apply_timeout(), Some(DEFAULT_APPLY_TIMEOUT));
+
+    let token = token.with_apply_timeout(None);
+    assert_eq!(token.apply_timeout(), None);
+}
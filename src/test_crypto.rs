@@ -0,0 +1,99 @@
+use super::*;
+
+use std::str::FromStr;
+
+fn hex(input: &str) -> Vec<u8> {
+    (0..input.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&input[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn session_keys() {
+    let application_key = Key::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+
+    let session_keys = derive_session_keys(&application_key, 0x123456, 0x000013, 0xabcd);
+
+    assert_eq!(
+        session_keys.network_session_key().digits().to_vec(),
+        hex("c670e85756697041d692598007458424")
+    );
+    assert_eq!(
+        session_keys.application_session_key().digits().to_vec(),
+        hex("0ff0a4657a03ca86a5740ff1da314e3d")
+    );
+}
+
+#[test]
+fn data_frame() {
+    let network_session_key = Key::from_str("44024241ed4ce9a68c6a8bc055233fd3").unwrap();
+    let application_session_key = Key::from_str("ec925802ae430ca77fd3dd73cb2cc588").unwrap();
+    let phy_payload = hex("40f17dbe4900020001954378762b11ff0d");
+
+    assert!(verify_data_mic(
+        &network_session_key,
+        Direction::Uplink,
+        0x49be7df1,
+        2,
+        &phy_payload
+    )
+    .is_ok());
+    assert!(matches!(
+        verify_data_mic(
+            &network_session_key,
+            Direction::Uplink,
+            0x49be7df1,
+            3,
+            &phy_payload
+        ),
+        Err(MtcapError::InvalidMic)
+    ));
+    assert!(verify_data_mic(&network_session_key, Direction::Uplink, 0, 0, &[0x40]).is_err());
+
+    let payload = decrypt_frm_payload(
+        &application_session_key,
+        Direction::Uplink,
+        0x49be7df1,
+        2,
+        &phy_payload[9..13],
+    );
+    assert_eq!(payload, b"test");
+    assert_eq!(
+        encrypt_frm_payload(
+            &application_session_key,
+            Direction::Uplink,
+            0x49be7df1,
+            2,
+            &payload
+        ),
+        phy_payload[9..13]
+    );
+}
+
+#[test]
+fn join_request() {
+    let application_key = Key::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    let phy_payload = hex("0008070605040302011817161514131211cdab758c4d8c");
+
+    assert!(verify_join_mic(&application_key, &phy_payload).is_ok());
+    assert!(verify_join_mic(&application_key, &phy_payload[1..]).is_err());
+}
+
+#[test]
+fn join_accept() {
+    let application_key = Key::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    let encrypted = hex("2032f58755bc4802ac960d81f0c75cbfef");
+    let decrypted = hex("20563412130000341201260001f8cbda35");
+
+    assert_eq!(
+        decrypt_join_accept(&application_key, &encrypted).unwrap(),
+        decrypted
+    );
+    assert_eq!(
+        encrypt_join_accept(&application_key, &decrypted).unwrap(),
+        encrypted
+    );
+    assert!(verify_join_mic(&application_key, &decrypted).is_ok());
+    assert!(decrypt_join_accept(&application_key, &encrypted[..10]).is_err());
+}
@@ -0,0 +1,24 @@
+use std::thread;
+
+use super::*;
+use crate::test_server::{serve, success};
+
+#[test]
+fn concurrent_requests() {
+    const REQUESTS: usize = 8;
+    let address = serve(|request| success(json::parse(&request.body).unwrap()));
+
+    thread::scope(|scope| {
+        let requests: Vec<_> = (0..REQUESTS)
+            .map(|i| {
+                let url = format!("{address}/api");
+                scope.spawn(move || post_json_with_response(url, json::object! { request: i }))
+            })
+            .collect();
+
+        for (i, request) in requests.into_iter().enumerate() {
+            let response = request.join().unwrap().unwrap();
+            assert_eq!(json::parse(&response).unwrap()["result"]["request"], i);
+        }
+    });
+}
@@ -35,3 +35,297 @@ fn key_from_str() {
         ]
     );
 }
+
+#[test]
+fn key_generate() {
+    let key_0 = Key::generate().unwrap();
+    let key_1 = Key::generate().unwrap();
+
+    assert_ne!(key_0.digits, key_1.digits);
+}
+
+#[test]
+fn key_derive() {
+    let master = Key::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    let device_eui_0 = Eui::from_str("00-11-22-33-44-55-66-77").unwrap();
+    let device_eui_1 = Eui::from_str("00-11-22-33-44-55-66-78").unwrap();
+
+    assert_eq!(
+        Key::derive(&master, &device_eui_0).digits,
+        [
+            0xa5, 0xd9, 0x9f, 0x8b, 0xd8, 0xbc, 0xbe, 0x03, 0xd6, 0xc7, 0xd1, 0x55, 0x48, 0x05,
+            0x82, 0x42
+        ]
+    );
+    assert_eq!(
+        Key::derive(&master, &device_eui_0).digits,
+        Key::derive(&master, &device_eui_0).digits
+    );
+    assert_ne!(
+        Key::derive(&master, &device_eui_0).digits,
+        Key::derive(&master, &device_eui_1).digits
+    );
+}
+
+#[test]
+fn device_profile_from_id() {
+    assert_eq!(
+        DeviceProfile::from_id("LW102-OTA-AS923-2"),
+        Some(DeviceProfile::As923_2)
+    );
+    assert_eq!(
+        DeviceProfile::from_id("LW102-OTA-US915"),
+        Some(DeviceProfile::Us915)
+    );
+    assert_eq!(DeviceProfile::from_id("US915"), None);
+    assert_eq!(DeviceProfile::from_id("LW102-OTA-XX000"), None);
+}
+
+fn devices_json() -> json::JsonValue {
+    json::array![
+        {
+            deveui: "00-00-00-00-00-00-00-01",
+            class: "A",
+            device_profile_id: "LW102-OTA-EU868",
+            created_at: "2024-01-01T00:00:00Z",
+            last_seen: "2024-06-01T12:00:00Z",
+        },
+        {
+            deveui: "00-00-00-00-00-00-00-02",
+            class: "C",
+            device_profile_id: "LW102-OTA-US915",
+            created_at: "2024-01-01T00:00:00Z",
+            last_seen: null,
+        },
+        {
+            deveui: "00-00-00-00-00-00-01-00",
+            class: "A",
+            device_profile_id: "LW102-OTA-EU868",
+            created_at: "2024-01-01T00:00:00Z",
+            last_seen: "2024-09-01T12:00:00Z",
+        },
+        {
+            deveui: "00-00-00-00-00-00-01-01",
+            class: "A",
+            device_profile_id: "LW102-OTA-EU868",
+            created_at: "2024-01-01T00:00:00Z",
+            last_seen: "yesterday",
+        },
+    ]
+}
+
+fn selected_euis(filter: &Filter) -> Vec<String> {
+    Selection::from_json(&devices_json(), filter)
+        .devices()
+        .iter()
+        .map(|device| device.device_eui().to_string())
+        .collect()
+}
+
+#[test]
+fn filter() {
+    let time = |input: &str| DateTime::parse_from_rfc3339(input).unwrap().to_utc();
+
+    let selection = Selection::from_json(&devices_json(), &Filter::new());
+    assert_eq!(selection.devices().len(), 3);
+    assert_eq!(selection.unparseable().len(), 1);
+    assert!(selection.unparseable()[0].json().contains("yesterday"));
+
+    assert_eq!(
+        selected_euis(&Filter::new().last_seen_before(time("2024-07-01T00:00:00Z"))),
+        ["00-00-00-00-00-00-00-01", "00-00-00-00-00-00-00-02"]
+    );
+    assert_eq!(
+        selected_euis(&Filter::new().last_seen_after(time("2024-07-01T00:00:00Z"))),
+        ["00-00-00-00-00-00-01-00"]
+    );
+    assert_eq!(
+        selected_euis(&Filter::new().never_joined()),
+        ["00-00-00-00-00-00-00-02"]
+    );
+    assert_eq!(
+        selected_euis(
+            &Filter::new()
+                .class(Class::A)
+                .device_profile(DeviceProfile::Eu868)
+        ),
+        ["00-00-00-00-00-00-00-01", "00-00-00-00-00-00-01-00"]
+    );
+    assert_eq!(
+        selected_euis(&Filter::new().eui_prefix(&[0, 0, 0, 0, 0, 0, 1])),
+        ["00-00-00-00-00-00-01-00"]
+    );
+    assert_eq!(
+        selected_euis(&Filter::new().eui_range(
+            Eui::from_str("00-00-00-00-00-00-00-02").unwrap(),
+            Eui::from_str("00-00-00-00-00-00-01-00").unwrap()
+        )),
+        ["00-00-00-00-00-00-00-02", "00-00-00-00-00-00-01-00"]
+    );
+}
+
+#[test]
+fn device_snapshot_round_trip() {
+    let snapshot = DeviceSnapshot {
+        allowlist: json::object! {
+            enabled: true,
+            devices: [{ deveui: "00-00-00-00-00-00-00-01" }],
+        },
+        devices: json::array![
+            { deveui: "00-00-00-00-00-00-00-01", dev_addr: "01020304" },
+            { deveui: "00-00-00-00-00-00-00-02", dev_addr: "" },
+        ],
+    };
+
+    let path = std::env::temp_dir().join("mtcap_test_device_snapshot.json");
+    snapshot.save(&path).unwrap();
+    let loaded = DeviceSnapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, snapshot);
+    assert_eq!(
+        loaded.allowlist().unwrap(),
+        [Eui::new([0, 0, 0, 0, 0, 0, 0, 1])]
+    );
+    assert_eq!(loaded.devices().unwrap().len(), 2);
+}
+
+#[test]
+fn merge_pages() {
+    let page = |range: std::ops::Range<usize>| {
+        range
+            .map(|i| {
+                let device_eui = Eui::new([0, 0, 0, 0, 0, 0, (i / 256) as u8, (i % 256) as u8]);
+                json::object! { deveui: device_eui.to_string() }
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut devices_json = json::JsonValue::new_array();
+    let mut device_euis = HashSet::new();
+
+    let first = page(0..PAGE_SIZE).into();
+    assert!(merge_page(&mut devices_json, &mut device_euis, &first).unwrap());
+    let short = page(PAGE_SIZE..PAGE_SIZE + 5).into();
+    assert!(!merge_page(&mut devices_json, &mut device_euis, &short).unwrap());
+    assert_eq!(devices_json.len(), PAGE_SIZE + 5);
+
+    // Firmware which ignores paging answers with the first page again.
+    let mut devices_json = json::JsonValue::new_array();
+    let mut device_euis = HashSet::new();
+    assert!(merge_page(&mut devices_json, &mut device_euis, &first).unwrap());
+    assert!(!merge_page(&mut devices_json, &mut device_euis, &first).unwrap());
+    assert_eq!(devices_json.len(), PAGE_SIZE);
+}
+
+#[test]
+fn bulk_removal_resume() {
+    use std::sync::Mutex;
+
+    use crate::credentials::Token;
+    use crate::test_server::{failure, serve, success};
+
+    let failed_once = Mutex::new(false);
+    let address = serve(move |request| {
+        assert_eq!(request.method, "DELETE");
+        let mut failed_once = failed_once.lock().unwrap();
+        if request.path.ends_with("00-00-00-00-00-00-00-02") && !*failed_once {
+            *failed_once = true;
+            return failure("busy");
+        }
+        success(json::Null)
+    });
+    let token = Token::new(address, "token".to_string());
+
+    let device_euis = [
+        "00-00-00-00-00-00-00-01",
+        "00-00-00-00-00-00-00-02",
+        "00-00-00-00-00-00-00-03",
+    ];
+    let mut removal = BulkRemoval {
+        allowlist_update: AllowlistUpdate::None,
+        allowlist_done: false,
+        pending: device_euis.iter().map(ToString::to_string).collect(),
+        removed: Vec::new(),
+        failed: Vec::new(),
+        snapshot_path: None,
+    };
+
+    let mut errors = 0;
+    assert!(removal
+        .run(&token, |progress| errors +=
+            usize::from(progress.error.is_some()))
+        .is_err());
+    assert_eq!(errors, 1);
+    assert_eq!(removal.failed(), ["00-00-00-00-00-00-00-02"]);
+    assert!(!removal.is_complete());
+
+    removal.run(&token, |_| {}).unwrap();
+    assert!(removal.is_complete());
+    assert_eq!(
+        removal.removed(),
+        [
+            "00-00-00-00-00-00-00-01",
+            "00-00-00-00-00-00-00-03",
+            "00-00-00-00-00-00-00-02"
+        ]
+    );
+}
+
+#[test]
+fn rollback_skips_malformed_devices() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::credentials::Token;
+    use crate::test_server::{failure, serve, success};
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let address = serve({
+        let requests = Arc::clone(&requests);
+        move |request| {
+            let path = request.path.split('?').next().unwrap_or_default();
+            requests
+                .lock()
+                .unwrap()
+                .push(format!("{} {path}", request.method));
+            match (request.method.as_str(), path) {
+                ("GET", "/api/lora/devices") => success(json::array![
+                    { deveui: "00-00-00-00-00-00-00-01" },
+                    { deveui: "not an eui" },
+                ]),
+                ("POST", "/api/lora/devices") => {
+                    match json::parse(&request.body).unwrap()["deveui"].as_str() {
+                        Some("00-00-00-00-00-00-00-03") => failure("refused"),
+                        _ => success(json::Null),
+                    }
+                }
+                _ => success(json::Null),
+            }
+        }
+    });
+    let token = Token::new(address, "token".to_string()).with_apply_timeout(None);
+
+    let snapshot = DeviceSnapshot {
+        allowlist: json::object! { enabled: true, devices: [] },
+        devices: json::array![
+            { deveui: "00-00-00-00-00-00-00-01" },
+            { deveui: "00-00-00-00-00-00-00-02", created_at: "2024-01-01T00:00:00Z" },
+            { deveui: "bad" },
+            { deveui: "00-00-00-00-00-00-00-03" },
+        ],
+    };
+
+    let rollback = rollback(&token, &snapshot).unwrap();
+    assert_eq!(rollback.sessions_created(), ["00-00-00-00-00-00-00-02"]);
+    assert_eq!(
+        rollback
+            .sessions_failed()
+            .iter()
+            .map(|(device_eui, _)| device_eui.as_str())
+            .collect::<Vec<_>>(),
+        ["bad", "00-00-00-00-00-00-00-03"]
+    );
+    assert_eq!(
+        requests.lock().unwrap().last().unwrap(),
+        "POST /api/command/save_apply"
+    );
+}
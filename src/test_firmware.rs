@@ -0,0 +1,38 @@
+use std::fs;
+
+use super::*;
+
+#[test]
+fn checksum() {
+    let path = std::env::temp_dir().join("mtcap_test_firmware_checksum.bin");
+    fs::write(&path, b"abc").unwrap();
+
+    let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert_eq!(sha256(&path).unwrap(), expected);
+    assert!(verify_checksum(&path, &expected.to_ascii_uppercase()).is_ok());
+    assert!(matches!(
+        verify_checksum(&path, &expected.replace('b', "c")),
+        Err(MtcapError::ChecksumMismatch { .. })
+    ));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn status_from_json() {
+    let status = UpgradeStatus::from_json(&json::object! {
+        state: "installing",
+        progress: 42,
+        message: "Writing rootfs",
+    })
+    .unwrap();
+    assert_eq!(status.state(), UpgradeState::Installing);
+    assert_eq!(status.percent(), Some(42));
+    assert_eq!(status.message(), "Writing rootfs");
+
+    let status = UpgradeStatus::from_json(&json::object! {}).unwrap();
+    assert_eq!(status.state(), UpgradeState::Idle);
+    assert_eq!(status.percent(), None);
+
+    assert!(UpgradeStatus::from_json(&json::object! { state: "exploded" }).is_err());
+}
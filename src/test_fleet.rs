@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::*;
+use crate::test_server::{failure, serve, success};
+
+/// A gateway which hands out `token`, or refuses logins without one, and counts logouts.
+fn gateway(token: Option<&'static str>, logouts: &Arc<AtomicUsize>) -> Gateway {
+    let logouts = Arc::clone(logouts);
+    let address = serve(move |request| {
+        if request.path.starts_with("/api/login") {
+            match token {
+                Some(token) => success(json::object! { token: token }),
+                None => failure("Invalid credentials"),
+            }
+        } else {
+            logouts.fetch_add(1, Ordering::Relaxed);
+            success(json::Null)
+        }
+    });
+
+    Gateway::with_address(address, "admin".to_string(), "password".to_string())
+}
+
+#[test]
+fn results_in_order() {
+    let logouts = Arc::new(AtomicUsize::new(0));
+    let gateways = [
+        gateway(Some("a"), &logouts),
+        gateway(None, &logouts),
+        gateway(Some("c"), &logouts),
+        gateway(Some("d"), &logouts),
+    ];
+
+    let report = run(&gateways, 2, |token| match token.token() {
+        "d" => Err(MtcapError::Other("operation failed".to_string())),
+        token => Ok(token.to_string()),
+    });
+
+    let results = report.results();
+    assert_eq!(results[0].as_ref().unwrap(), "a");
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), "c");
+    assert!(results[3].is_err());
+    assert_eq!(
+        report
+            .failures()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+        [1, 3]
+    );
+    // Every gateway logged into is logged out of, even when the operation fails.
+    assert_eq!(logouts.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn concurrency() {
+    let logouts = Arc::new(AtomicUsize::new(0));
+    let gateways = [
+        gateway(Some("a"), &logouts),
+        gateway(Some("b"), &logouts),
+        gateway(Some("c"), &logouts),
+    ];
+
+    for (concurrency, expected) in [(0, 1), (2, 2), (10, 3)] {
+        let running = AtomicUsize::new(0);
+        let most_running = AtomicUsize::new(0);
+        let report = run(&gateways, concurrency, |token| {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now_running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(200));
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(token.token().to_string())
+        });
+
+        assert!(report.is_success());
+        assert_eq!(
+            report
+                .into_results()
+                .into_iter()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(most_running.load(Ordering::SeqCst), expected);
+    }
+}
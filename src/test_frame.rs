@@ -0,0 +1,196 @@
+use super::*;
+
+use std::str::FromStr;
+
+fn hex(input: &str) -> Vec<u8> {
+    (0..input.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&input[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn data_frame() {
+    let bytes = hex("40f17dbe4900020001954378762b11ff0d");
+
+    let PhyPayload::Data(frame) = PhyPayload::parse(&bytes).unwrap() else {
+        panic!("not a data frame");
+    };
+    assert_eq!(frame.message_type(), MessageType::UnconfirmedDataUp);
+    assert_eq!(frame.fhdr().device_address(), 0x49be7df1);
+    assert_eq!(frame.fhdr().fctrl(), FCtrl::default());
+    assert_eq!(frame.fhdr().frame_count(), 2);
+    assert!(frame.fhdr().fopts().is_empty());
+    assert_eq!(frame.port(), Some(1));
+    assert_eq!(frame.frm_payload(), hex("95437876"));
+    assert_eq!(frame.mic(), [0x2b, 0x11, 0xff, 0x0d]);
+    assert_eq!(frame.to_bytes(), bytes);
+
+    let network_session_key = Key::from_str("44024241ed4ce9a68c6a8bc055233fd3").unwrap();
+    assert!(frame.verify_mic(&network_session_key, 2).is_ok());
+
+    let mut rebuilt = DataFrame::new(
+        false,
+        Direction::Uplink,
+        Fhdr::new(0x49be7df1, FCtrl::default(), 2, Vec::new()).unwrap(),
+        Some(1),
+        hex("95437876"),
+    );
+    rebuilt.sign(&network_session_key, 2);
+    assert_eq!(rebuilt, frame);
+}
+
+#[test]
+fn fopts() {
+    let fhdr = Fhdr::new(
+        0x01020304,
+        FCtrl {
+            adr: true,
+            ack: true,
+            ..FCtrl::default()
+        },
+        0x1234,
+        vec![
+            MacCommand::LinkCheckReq,
+            MacCommand::DevStatusAns {
+                battery: 0xff,
+                margin: -5,
+            },
+        ],
+    )
+    .unwrap();
+    let frame = DataFrame::new(true, Direction::Uplink, fhdr, None, Vec::new());
+
+    let bytes = frame.to_bytes();
+    assert_eq!(bytes, hex("8004030201a434120206ff3b00000000"));
+    assert_eq!(DataFrame::parse(&bytes).unwrap(), frame);
+
+    assert!(Fhdr::new(0, FCtrl::default(), 0, vec![MacCommand::LinkCheckReq; 16]).is_err());
+}
+
+#[test]
+fn mac_commands() {
+    let commands = vec![
+        MacCommand::LinkAdrReq {
+            data_rate: 5,
+            tx_power: 2,
+            channel_mask: 0x00ff,
+            redundancy: 0x01,
+        },
+        MacCommand::RxParamSetupReq {
+            dl_settings: 0x03,
+            frequency: 869_525_000,
+        },
+        MacCommand::DeviceTimeAns {
+            seconds: 1_000_000_000,
+            fractional: 0x80,
+        },
+    ];
+    let bytes: Vec<u8> = commands.iter().flat_map(MacCommand::to_bytes).collect();
+
+    assert_eq!(
+        MacCommand::parse_all(Direction::Downlink, &bytes).unwrap(),
+        commands
+    );
+    assert!(MacCommand::parse_all(Direction::Downlink, &[0x03, 0x52]).is_err());
+    assert_eq!(
+        MacCommand::parse_all(Direction::Uplink, &[0x04, 0x20, 0xaa, 0xbb]).unwrap(),
+        [
+            MacCommand::DutyCycleAns,
+            MacCommand::Unknown {
+                cid: 0x20,
+                payload: vec![0xaa, 0xbb],
+            },
+        ]
+    );
+}
+
+#[test]
+fn class_b_mac_commands() {
+    let uplink = vec![
+        MacCommand::PingSlotInfoReq { periodicity: 3 },
+        MacCommand::PingSlotChannelAns { status: 0x03 },
+        MacCommand::BeaconTimingReq,
+        MacCommand::BeaconFreqAns { status: 0x01 },
+    ];
+    let bytes: Vec<u8> = uplink.iter().flat_map(MacCommand::to_bytes).collect();
+    assert_eq!(
+        MacCommand::parse_all(Direction::Uplink, &bytes).unwrap(),
+        uplink
+    );
+
+    let downlink = vec![
+        MacCommand::PingSlotInfoAns,
+        MacCommand::PingSlotChannelReq {
+            frequency: 869_525_000,
+            data_rate: 3,
+        },
+        MacCommand::BeaconTimingAns {
+            delay: 1000,
+            channel: 0,
+        },
+        MacCommand::BeaconFreqReq {
+            frequency: 869_525_000,
+        },
+    ];
+    let bytes: Vec<u8> = downlink.iter().flat_map(MacCommand::to_bytes).collect();
+    assert_eq!(
+        MacCommand::parse_all(Direction::Downlink, &bytes).unwrap(),
+        downlink
+    );
+}
+
+#[test]
+fn join_request() {
+    let bytes = hex("0008070605040302011817161514131211cdab758c4d8c");
+
+    let PhyPayload::JoinRequest(join_request) = PhyPayload::parse(&bytes).unwrap() else {
+        panic!("not a join-request");
+    };
+    assert_eq!(
+        join_request.join_eui(),
+        &Eui::from_str("01-02-03-04-05-06-07-08").unwrap()
+    );
+    assert_eq!(
+        join_request.device_eui(),
+        &Eui::from_str("11-12-13-14-15-16-17-18").unwrap()
+    );
+    assert_eq!(join_request.device_nonce(), 0xabcd);
+    assert_eq!(join_request.to_bytes(), bytes);
+
+    let application_key = Key::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    assert!(join_request.verify_mic(&application_key).is_ok());
+}
+
+#[test]
+fn join_accept() {
+    let application_key = Key::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    let bytes = hex("2032f58755bc4802ac960d81f0c75cbfef");
+
+    let PhyPayload::EncryptedJoinAccept(encrypted) = PhyPayload::parse(&bytes).unwrap() else {
+        panic!("not a join-accept");
+    };
+    let decrypted = crypto::decrypt_join_accept(&application_key, &encrypted).unwrap();
+    let join_accept = JoinAccept::parse(&decrypted).unwrap();
+    assert_eq!(join_accept.join_nonce(), 0x123456);
+    assert_eq!(join_accept.net_id(), 0x000013);
+    assert_eq!(join_accept.device_address(), 0x26011234);
+    assert_eq!(join_accept.rx_delay(), 1);
+    assert!(join_accept.cf_list().is_none());
+    assert!(join_accept.verify_mic(&application_key).is_ok());
+
+    let mut rebuilt = JoinAccept::new(0x123456, 0x000013, 0x26011234, 0x00, 0x01, None);
+    rebuilt.sign(&application_key);
+    assert_eq!(rebuilt.encrypt(&application_key), bytes);
+}
+
+#[test]
+fn invalid() {
+    assert!(PhyPayload::parse(&[]).is_err());
+    assert!(PhyPayload::parse(&[0x40, 0x00]).is_err());
+    assert!(PhyPayload::parse(&hex("41f17dbe4900020001954378762b11ff0d")).is_err());
+    assert!(matches!(
+        PhyPayload::parse(&[0x20; 5]),
+        Err(MtcapError::InvalidFrame(_))
+    ));
+}
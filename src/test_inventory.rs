@@ -0,0 +1,70 @@
+use super::*;
+
+#[test]
+fn toml() {
+    let inventory = Inventory::from_toml(
+        r#"
+        [[gateways]]
+        name = "site-a"
+        address = "192.168.2.1"
+        username = "admin"
+        password = { env = "MTCAP_TEST_INVENTORY_PASSWORD" }
+
+        [[gateways]]
+        name = "site-b"
+        address = "10.0.0.2"
+        username = "admin"
+        password = { command = "pass show site-b" }
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(inventory.entries().len(), 2);
+    assert_eq!(inventory.entry("site-b").unwrap().address(), "10.0.0.2");
+    assert_eq!(
+        inventory.entry("site-b").unwrap().password_source(),
+        &PasswordSource::Command("pass show site-b".to_string())
+    );
+    assert!(inventory.entry("site-c").is_err());
+
+    std::env::set_var("MTCAP_TEST_INVENTORY_PASSWORD", "secret");
+    assert!(inventory.entry("site-a").unwrap().gateway().is_ok());
+    std::env::remove_var("MTCAP_TEST_INVENTORY_PASSWORD");
+    assert!(inventory.entry("site-a").unwrap().gateway().is_err());
+}
+
+#[test]
+fn json() {
+    let inventory = Inventory::from_json(
+        r#"{
+            "gateways": [
+                {
+                    "name": "site-a",
+                    "address": "192.168.2.1",
+                    "username": "admin",
+                    "password": { "file": "password.txt" }
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        inventory.entries()[0].password_source(),
+        &PasswordSource::File(PathBuf::from("password.txt"))
+    );
+    assert!(Inventory::from_json(r#"{ "gateways": [ { "name": "site-a" } ] }"#).is_err());
+}
+
+#[test]
+fn password_file() {
+    let path = std::env::temp_dir().join("mtcap_test_inventory_password.txt");
+    fs::write(&path, "secret\nignored\n").unwrap();
+
+    assert_eq!(
+        PasswordSource::File(path.clone()).resolve().unwrap(),
+        "secret"
+    );
+
+    fs::remove_file(path).unwrap();
+}
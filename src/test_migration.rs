@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn merge() {
+    let mut destination_json = json::object! {
+        devices: [
+            { deveui: "00-00-00-00-00-00-00-01", appkey: "old" },
+            { deveui: "00-00-00-00-00-00-00-02", appkey: "kept" },
+        ],
+        enabled: true,
+    };
+    let source_json = json::object! {
+        devices: [
+            { deveui: "00-00-00-00-00-00-00-01", appkey: "new" },
+            { deveui: "00-00-00-00-00-00-00-03", appkey: "added" },
+        ],
+        enabled: false,
+    };
+
+    let device_euis = merge_allowlist(&mut destination_json, &source_json).unwrap();
+
+    assert_eq!(
+        device_euis,
+        [
+            Eui::from_str("00-00-00-00-00-00-00-01").unwrap(),
+            Eui::from_str("00-00-00-00-00-00-00-03").unwrap(),
+        ]
+    );
+    assert_eq!(
+        destination_json["devices"],
+        json::array![
+            { deveui: "00-00-00-00-00-00-00-01", appkey: "new" },
+            { deveui: "00-00-00-00-00-00-00-02", appkey: "kept" },
+            { deveui: "00-00-00-00-00-00-00-03", appkey: "added" },
+        ]
+    );
+    assert_eq!(destination_json["enabled"], false);
+
+    let mut destination_json = json::object! { devices: [], enabled: true };
+    merge_allowlist(&mut destination_json, &json::object! { devices: [] }).unwrap();
+    assert_eq!(destination_json["enabled"], true);
+}
+
+#[test]
+fn verify_fields() {
+    let entry = |appkey: &str| {
+        json::object! {
+            deveui: "00-00-00-00-00-00-00-01",
+            appeui: "00-00-00-00-00-00-00-0a",
+            appkey: appkey,
+            class: "A",
+            device_profile_id: "LW102-OTA-EU868",
+            network_profile_id: "DEFAULT-CLASS-A",
+        }
+    };
+    let source_json = json::array![entry("000102030405060708090a0b0c0d0e0f")];
+    let devices = [Eui::from_str("00-00-00-00-00-00-00-01").unwrap()];
+
+    let destination_json = json::array![entry("000102030405060708090A0B0C0D0E0F")];
+    assert!(verify_allowlist(&source_json, &destination_json, &devices).is_ok());
+
+    let destination_json = json::array![entry("ffffffffffffffffffffffffffffffff")];
+    assert!(verify_allowlist(&source_json, &destination_json, &devices).is_err());
+
+    let destination_json = json::array![];
+    assert!(verify_allowlist(&source_json, &destination_json, &devices).is_err());
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+#[test]
+fn ethernet_from_json() {
+    let config = EthernetConfig::from_json(&json::object! {
+        name: "eth0",
+        ipv4: {
+            mode: "STATIC",
+            ip: "192.168.2.1",
+            mask: "255.255.255.0",
+            gateway: "192.168.2.254",
+            dns1: "8.8.8.8",
+            dns2: "",
+        },
+    })
+    .unwrap();
+    assert_eq!(
+        config,
+        EthernetConfig::Static(StaticIpv4 {
+            address: Ipv4Addr::new(192, 168, 2, 1),
+            netmask: Ipv4Addr::new(255, 255, 255, 0),
+            gateway: Some(Ipv4Addr::new(192, 168, 2, 254)),
+            dns: vec![Ipv4Addr::new(8, 8, 8, 8)],
+        })
+    );
+
+    let config = EthernetConfig::from_json(&json::object! {
+        ipv4: { mode: "DHCP-CLIENT", ip: "" },
+    })
+    .unwrap();
+    assert_eq!(config, EthernetConfig::Dhcp);
+
+    assert!(EthernetConfig::from_json(&json::object! { ipv4: { mode: "PPP" } }).is_err());
+    assert!(EthernetConfig::from_json(&json::object! {
+        ipv4: { mode: "STATIC", ip: "192.168.2.1", mask: "" },
+    })
+    .is_err());
+}
+
+#[test]
+fn ethernet_update_json() {
+    let mut json = json::object! {
+        name: "eth0",
+        ipv4: { mode: "DHCP-CLIENT", ip: "", mask: "", gateway: "", dns1: "", dns2: "" },
+    };
+    let config = EthernetConfig::Static(StaticIpv4 {
+        address: Ipv4Addr::new(10, 0, 0, 2),
+        netmask: Ipv4Addr::new(255, 255, 0, 0),
+        gateway: None,
+        dns: vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(1, 1, 1, 1)],
+    });
+
+    config.update_json(&mut json).unwrap();
+    assert_eq!(json["name"], "eth0");
+    assert_eq!(EthernetConfig::from_json(&json).unwrap(), config);
+}
+
+#[test]
+fn ethernet_new_address() {
+    let config = EthernetConfig::Static(StaticIpv4 {
+        address: Ipv4Addr::new(10, 0, 0, 2),
+        netmask: Ipv4Addr::new(255, 255, 255, 0),
+        gateway: None,
+        dns: Vec::new(),
+    });
+    let old_address = Some(Ipv4Addr::new(192, 168, 2, 1));
+
+    let address = Address::from_str("192.168.2.1:8443").unwrap();
+    assert_eq!(
+        new_address(&address, old_address, &config),
+        Address::from_str("10.0.0.2:8443").unwrap()
+    );
+
+    for address in ["gateway.example.com", "203.0.113.7:8443"] {
+        let address = Address::from_str(address).unwrap();
+        assert_eq!(new_address(&address, old_address, &config), address);
+    }
+    let address = Address::from_str("192.168.2.1").unwrap();
+    assert_eq!(
+        new_address(&address, old_address, &EthernetConfig::Dhcp),
+        address
+    );
+}
+
+#[test]
+fn packet_forwarder_round_trip() {
+    let mut json = json::object! {
+        enabled: true,
+        packetForwarderMode: true,
+        channelPlan: "EU868",
+        packetForwarder: {
+            serverAddress: "router.eu.thethings.network",
+            upstreamPort: 1700,
+            downstreamPort: 1700,
+            keepAliveInterval: 10,
+            gatewayId: "00800000a0001234",
+            statInterval: 30,
+        },
+    };
+
+    let config = PacketForwarderConfig::from_json(&json).unwrap();
+    assert_eq!(config.server_address, "router.eu.thethings.network");
+    assert_eq!(config.keepalive, Duration::from_secs(10));
+    assert_eq!(
+        config.gateway_id,
+        Eui::new([0x00, 0x80, 0x00, 0x00, 0xa0, 0x00, 0x12, 0x34])
+    );
+    assert_eq!(config.channel_plan, DeviceProfile::Eu868);
+    assert_eq!(config.frequency_sub_band, None);
+
+    let config = PacketForwarderConfig {
+        server_address: "lns.example.com".to_string(),
+        upstream_port: 1701,
+        downstream_port: 1702,
+        channel_plan: DeviceProfile::Us915,
+        frequency_sub_band: Some(2),
+        ..config
+    };
+    config.update_json(&mut json);
+    assert_eq!(json["packetForwarder"]["statInterval"], 30);
+    assert_eq!(json["packetForwarder"]["gatewayId"], "00800000a0001234");
+    assert_eq!(PacketForwarderConfig::from_json(&json).unwrap(), config);
+}
+
+#[test]
+fn gateway_id_format() {
+    let eui = Eui::new([0x00, 0x80, 0x00, 0x00, 0xa0, 0x00, 0x12, 0x34]);
+    assert_eq!(format_like(&eui, None), "00800000a0001234");
+    assert_eq!(
+        format_like(&eui, Some("00800000A0001234")),
+        "00800000A0001234"
+    );
+    assert_eq!(
+        format_like(&eui, Some("00-80-00-00-a0-00-12-34")),
+        "00-80-00-00-a0-00-12-34"
+    );
+    assert_eq!(
+        format_like(&eui, Some("00:80:00:00:A0:00:12:34")),
+        "00:80:00:00:A0:00:12:34"
+    );
+}
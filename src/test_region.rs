@@ -0,0 +1,141 @@
+use super::*;
+
+#[test]
+fn eu868() {
+    let parameters = DeviceProfile::Eu868.regional_parameters();
+
+    assert_eq!(
+        parameters.uplink_channels(),
+        [868_100_000, 868_300_000, 868_500_000]
+    );
+    assert!(parameters.downlink_channels().is_empty());
+    assert_eq!(parameters.max_payload_size(0), Some(51));
+    assert_eq!(parameters.max_payload_size(5), Some(242));
+    assert_eq!(parameters.max_payload_size(8), None);
+    assert_eq!(parameters.rx2_frequency(), 869_525_000);
+    assert_eq!(parameters.rx2_data_rate(), 0);
+    assert_eq!(parameters.duty_cycle(868_100_000), Some(0.01));
+    assert_eq!(parameters.duty_cycle(869_525_000), Some(0.1));
+}
+
+#[test]
+fn us915() {
+    let parameters = DeviceProfile::Us915.regional_parameters();
+
+    assert_eq!(parameters.uplink_channels().len(), 72);
+    assert_eq!(parameters.uplink_channels()[63], 914_900_000);
+    assert_eq!(parameters.uplink_channels()[71], 914_200_000);
+    assert_eq!(parameters.downlink_channels()[7], 927_500_000);
+    assert_eq!(parameters.max_payload_size(0), Some(11));
+    assert_eq!(parameters.max_payload_size(5), None);
+    assert_eq!(
+        parameters.data_rate(8).unwrap().modulation,
+        Modulation::Lora {
+            spreading_factor: 12,
+            bandwidth: 500_000
+        }
+    );
+    assert!(parameters.duty_cycle_bands().is_empty());
+}
+
+#[test]
+fn as923_variants() {
+    assert_eq!(
+        DeviceProfile::As923_2
+            .regional_parameters()
+            .uplink_channels(),
+        [921_400_000, 921_600_000]
+    );
+    assert_eq!(
+        DeviceProfile::As923_3.regional_parameters().rx2_frequency(),
+        916_600_000
+    );
+    assert_eq!(
+        DeviceProfile::As923_4
+            .regional_parameters()
+            .uplink_channels(),
+        [917_300_000, 917_500_000]
+    );
+    assert_eq!(DeviceProfile::As923_2.to_string(), "AS923-2");
+
+    let parameters = DeviceProfile::As923_3.regional_parameters();
+    assert_eq!(parameters.duty_cycle(916_600_000), Some(0.01));
+    assert_eq!(parameters.duty_cycle(927_000_000), Some(0.01));
+    assert_eq!(parameters.duty_cycle(910_000_000), None);
+}
+
+#[test]
+fn all_regions() {
+    for device_profile in [
+        DeviceProfile::As923,
+        DeviceProfile::As923_2,
+        DeviceProfile::As923_3,
+        DeviceProfile::As923_4,
+        DeviceProfile::Au915,
+        DeviceProfile::Cn470,
+        DeviceProfile::Cn779,
+        DeviceProfile::Eu433,
+        DeviceProfile::Eu868,
+        DeviceProfile::In865,
+        DeviceProfile::Kr920,
+        DeviceProfile::Ru864,
+        DeviceProfile::Us915,
+    ] {
+        let parameters = device_profile.regional_parameters();
+        assert!(!parameters.uplink_channels().is_empty());
+        assert!(parameters.data_rate(parameters.rx2_data_rate()).is_some());
+    }
+}
+
+#[test]
+fn payload_size() {
+    let parameters = DeviceProfile::Us915.regional_parameters();
+
+    assert!(parameters.check_payload_size(Some(0), 11).is_ok());
+    assert!(matches!(
+        parameters.check_payload_size(Some(0), 12),
+        Err(MtcapError::PayloadTooLarge {
+            length: 12,
+            max_length: 11
+        })
+    ));
+    assert!(parameters.check_payload_size(None, 242).is_ok());
+    assert!(parameters.check_payload_size(None, 243).is_err());
+    assert!(parameters.check_payload_size(Some(5), 1).is_err());
+
+    assert_eq!(parameters.find_data_rate("SF10BW125"), Some(0));
+    assert_eq!(parameters.find_data_rate("SF12BW500"), Some(8));
+    assert_eq!(parameters.find_data_rate("SF12BW125"), None);
+    assert_eq!(parameters.find_data_rate("50000"), None);
+}
+
+#[test]
+fn downlink_payload_size() {
+    let parameters = DeviceProfile::Us915.regional_parameters();
+    let uplink_data_rate = parameters.find_data_rate("SF10BW125");
+    assert_eq!(parameters.rx1_data_rate(0), Some(10));
+    assert_eq!(parameters.rx1_data_rate(4), Some(13));
+    assert!(parameters
+        .check_downlink_payload_size(uplink_data_rate, 242)
+        .is_ok());
+    assert!(parameters
+        .check_downlink_payload_size(uplink_data_rate, 243)
+        .is_err());
+    assert!(parameters.check_downlink_payload_size(Some(7), 53).is_ok());
+    assert!(parameters.check_downlink_payload_size(Some(7), 54).is_err());
+
+    let parameters = DeviceProfile::Au915.regional_parameters();
+    assert_eq!(parameters.rx1_data_rate(0), Some(8));
+    assert_eq!(parameters.rx1_data_rate(6), Some(13));
+
+    let parameters = DeviceProfile::Eu868.regional_parameters();
+    assert_eq!(parameters.rx1_data_rate(3), Some(3));
+    assert!(parameters.check_downlink_payload_size(Some(3), 115).is_ok());
+    assert!(matches!(
+        parameters.check_downlink_payload_size(Some(0), 52),
+        Err(MtcapError::PayloadTooLarge {
+            length: 52,
+            max_length: 51
+        })
+    ));
+}
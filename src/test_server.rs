@@ -0,0 +1,75 @@
+//! A loopback HTTP server standing in for a gateway in tests.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::thread;
+
+use crate::credentials::Address;
+
+pub(crate) struct Request {
+    pub method: String,
+    /// The path with its query, for example `/api/lora/devices?token=...`.
+    pub path: String,
+    pub body: String,
+}
+
+/// Answers requests on a loopback port with the JSON `handler` returns, until the test ends.
+pub(crate) fn serve<F>(handler: F) -> Address
+where
+    F: Fn(&Request) -> json::JsonValue + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = Address::from_str(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = json::stringify(handler(&Request {
+                method,
+                path,
+                body: String::from_utf8_lossy(&body).to_string(),
+            }));
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                response.len()
+            )
+            .unwrap();
+        }
+    });
+
+    address
+}
+
+pub(crate) fn success(result: json::JsonValue) -> json::JsonValue {
+    json::object! { status: "success", result: result }
+}
+
+pub(crate) fn failure(error: &str) -> json::JsonValue {
+    json::object! { status: "fail", error: error }
+}
@@ -0,0 +1,55 @@
+use super::*;
+
+#[test]
+fn from_json() {
+    let info = SystemInfo::from_json(&json::object! {
+        productId: "MTCAP-868-001A",
+        serialNumber: "20123456",
+        firmware: "6.0.1",
+        hardwareVersion: "MTCAP-0.1",
+        uptime: "3 days, 04:05:06",
+        macAddress: "00:08:00:4A:00:01",
+        macAddressBluetooth: "",
+        accessoryCards: [],
+    });
+
+    assert_eq!(info.product_id(), "MTCAP-868-001A");
+    assert_eq!(info.serial_number(), "20123456");
+    assert_eq!(info.firmware_version(), "6.0.1");
+    assert_eq!(info.hardware_version(), "MTCAP-0.1");
+    assert_eq!(
+        info.uptime(),
+        Some(Duration::from_secs(((3 * 24 + 4) * 60 + 5) * 60 + 6))
+    );
+    assert_eq!(info.mac_addresses(), ["00:08:00:4A:00:01"]);
+    assert_eq!(info.lora_card(), None);
+    assert_eq!(info.frequency_band(), Some(868));
+    assert_eq!(info.device_profile(), Some(DeviceProfile::Eu868));
+}
+
+#[test]
+fn lora_card() {
+    let info = SystemInfo::from_json(&json::object! {
+        productId: "MTCDT-L4N1-247A",
+        uptime: 3600,
+        accessoryCards: [
+            { productId: "MTAC-GPIOB" },
+            { productId: "MTAC-LORA-H-915" },
+        ],
+    });
+
+    assert_eq!(info.uptime(), Some(Duration::from_secs(3600)));
+    assert_eq!(info.lora_card(), Some("MTAC-LORA-H-915"));
+    assert_eq!(info.frequency_band(), Some(915));
+    assert_eq!(info.device_profile(), Some(DeviceProfile::Us915));
+}
+
+#[test]
+fn uptime() {
+    assert_eq!(
+        parse_uptime(&"12:34".into()),
+        Some(Duration::from_secs((12 * 60 + 34) * 60))
+    );
+    assert_eq!(parse_uptime(&"a while".into()), None);
+    assert_eq!(parse_uptime(&json::JsonValue::Null), None);
+}
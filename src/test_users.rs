@@ -0,0 +1,13 @@
+use super::*;
+
+#[test]
+fn user_from_json() {
+    let user = User::from_json(&json::object! { name: "admin", role: "Admin" }).unwrap();
+    assert_eq!(user.name(), "admin");
+    assert_eq!(user.role(), Role::Admin);
+
+    let user = User::from_json(&json::object! { name: "noc", role: "monitor" }).unwrap();
+    assert_eq!(user.role(), Role::Monitor);
+
+    assert!(User::from_json(&json::object! { name: "root", role: "superuser" }).is_err());
+}
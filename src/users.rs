@@ -0,0 +1,106 @@
+use strum_macros::{Display, EnumString};
+
+use crate::credentials::{get_url, login, logout, percent_encode, save_apply, Gateway, Token};
+use crate::curl;
+use crate::result::MtcapError;
+
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq)]
+pub enum Role {
+    #[strum(serialize = "admin")]
+    Admin,
+    #[strum(serialize = "engineer")]
+    Engineer,
+    #[strum(serialize = "monitor")]
+    Monitor,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct User {
+    name: String,
+    role: Role,
+}
+
+impl User {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    fn from_json(json: &json::JsonValue) -> Result<Self, MtcapError> {
+        let name = json["name"].as_str().unwrap_or_default().to_string();
+        let role = json["role"].as_str().unwrap_or_default();
+        let role = role
+            .to_ascii_lowercase()
+            .parse()
+            .map_err(|_| MtcapError::Other(format!("{name} has an unknown role {role}")))?;
+
+        Ok(Self { name, role })
+    }
+}
+
+pub fn list(token: &Token) -> Result<Vec<User>, MtcapError> {
+    let gateway_response = curl::get(get_url(token, "users"))?;
+
+    json::parse(&gateway_response)?["result"]
+        .members()
+        .map(User::from_json)
+        .collect()
+}
+
+pub fn create(token: &Token, name: &str, password: &str, role: Role) -> Result<(), MtcapError> {
+    let user_json = json::object! {
+        name: name,
+        password: password,
+        role: role.to_string(),
+    };
+    curl::post_json(get_url(token, "users"), user_json)?;
+
+    save_apply(token)?;
+
+    Ok(())
+}
+
+pub fn delete(token: &Token, name: &str) -> Result<(), MtcapError> {
+    curl::delete(get_url(token, format!("users/{}", percent_encode(name))))?;
+
+    save_apply(token)?;
+
+    Ok(())
+}
+
+pub fn set_password(token: &Token, name: &str, password: &str) -> Result<(), MtcapError> {
+    let gateway_response = curl::get(get_url(token, format!("users/{}", percent_encode(name))))?;
+    let mut user_json = json::parse(&gateway_response)?["result"].clone();
+    user_json["password"] = password.into();
+
+    curl::put(
+        get_url(token, format!("users/{}", percent_encode(name))),
+        user_json,
+    )?;
+
+    save_apply(token)?;
+
+    Ok(())
+}
+
+/// Changes the password of the user logged in as `gateway`, checks that the new password logs in,
+/// and returns the gateway with the new password.
+pub fn rotate_password(
+    token: &Token,
+    gateway: &Gateway,
+    password: &str,
+) -> Result<Gateway, MtcapError> {
+    set_password(token, gateway.username(), password)?;
+
+    let gateway = gateway.with_password(password.to_string());
+    let _ = logout(&login(&gateway)?);
+
+    Ok(gateway)
+}
+
+#[cfg(test)]
+#[path = "./test_users.rs"]
+mod test_users;